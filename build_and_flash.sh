@@ -1,5 +1,20 @@
 cp esp_rust/simulator/main.rs src/main.rs
+cp esp_rust/simulator/e32_module.rs src/simulator/e32_module.rs
 cp esp_rust/simulator/buffer.rs src/simulator/buffer.rs
+cp esp_rust/simulator/spsc.rs src/simulator/spsc.rs
+cp esp_rust/simulator/param_store.rs src/simulator/param_store.rs
+cp esp_rust/simulator/band.rs src/simulator/band.rs
+cp esp_rust/simulator/error.rs src/simulator/error.rs
+cp esp_rust/simulator/register_map.rs src/simulator/register_map.rs
+cp esp_rust/simulator/command_parser.rs src/simulator/command_parser.rs
+cp esp_rust/simulator/framer.rs src/simulator/framer.rs
+cp esp_rust/simulator/hal.rs src/simulator/hal.rs
+cp esp_rust/simulator/aux.rs src/simulator/aux.rs
+cp esp_rust/simulator/link.rs src/simulator/link.rs
+cp esp_rust/simulator/fault.rs src/simulator/fault.rs
+cp esp_rust/simulator/link_budget.rs src/simulator/link_budget.rs
+cp esp_rust/simulator/bridge.rs src/simulator/bridge.rs
+cp esp_rust/simulator/esp_hal.rs src/simulator/esp_hal.rs
 cargo build --release --target xtensa-esp32-espidf
 espflash flash target/xtensa-esp32-espidf/release/hello_world --chip esp32 --baud 460800 --port /dev/ttyUSB0
 #cp esp_rust/src/main2/main.rs src/main.rs
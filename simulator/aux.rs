@@ -0,0 +1,62 @@
+//! Mô hình thời gian của chân AUX theo datasheet E32
+
+use super::hal::AuxPin;
+
+/// Tự kiểm tra sau khi bật nguồn hoặc sau lệnh C4
+pub const SELF_CHECK_US: u64 = 30_000;
+/// AUX LOW trong lúc chuyển chế độ M0/M1
+pub const MODE_SWITCH_US: u64 = 1_000;
+/// Chế độ mới chỉ có hiệu lực 2 ms sau khi AUX lên HIGH
+pub const MODE_SETTLE_US: u64 = 2_000;
+/// AUX xuống LOW trước khi xuất dữ liệu nhận được ra UART, để đánh thức MCU
+pub const RX_NOTIFY_US: u64 = 2_000;
+/// Thời gian xử lý một lệnh cấu hình
+pub const CONFIG_BUSY_US: u64 = 1_000;
+
+pub struct AuxModel {
+    busy_until_us: u64,
+    level: Option<bool>, // mức đã ghi ra chân, None khi chưa ghi lần nào
+}
+
+impl AuxModel {
+    pub fn new() -> Self {
+        Self { busy_until_us: 0, level: None }
+    }
+
+    /// Giữ AUX LOW ít nhất `duration_us` kể từ `now_us`
+    pub fn hold_low(&mut self, now_us: u64, duration_us: u64) {
+        self.busy_until_us = self.busy_until_us.max(now_us + duration_us);
+    }
+
+    /// Module đang tự kiểm tra, chuyển chế độ hoặc xử lý lệnh
+    pub fn is_busy(&self, now_us: u64) -> bool {
+        now_us < self.busy_until_us
+    }
+
+    /// Tính mức AUX và chỉ ghi ra chân khi mức thay đổi.
+    /// `buffer_busy`: bộ đệm TX còn dữ liệu hoặc đang chuẩn bị xuất dữ liệu nhận.
+    pub fn update<A: AuxPin>(&mut self, aux: &mut A, now_us: u64, buffer_busy: bool) -> anyhow::Result<()> {
+        let high = !self.is_busy(now_us) && !buffer_busy;
+        if self.level != Some(high) {
+            if high {
+                aux.set_high()?;
+            } else {
+                aux.set_low()?;
+            }
+            self.level = Some(high);
+        }
+        Ok(())
+    }
+
+    // firmware không đọc lại mức AUX, chỉ bridge trên host dùng
+    #[allow(dead_code)]
+    pub fn is_high(&self) -> bool {
+        self.level.unwrap_or(true)
+    }
+}
+
+impl Default for AuxModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,71 @@
+//! Dải tần của từng dòng module: tần số gốc, bước kênh và số kênh.
+//! Tần số sóng mang = tần số gốc + CHAN * bước kênh.
+//! E22/E220 cùng dải có nhiều kênh hơn E32 và gốc lệch 125 kHz; bản 868 và 915
+//! của chúng là một module 900 MHz (850.125 ~ 930.125 MHz).
+
+use super::register_map::ModuleVariant;
+
+// firmware chỉ dựng dải chọn trong main.rs, các dải khác dùng trên host
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Band {
+    /// E32-170: 160 ~ 173.5 MHz, bước 0.5 MHz
+    Band170,
+    /// E32-433: 410 ~ 441 MHz, bước 1 MHz
+    Band433,
+    /// E32-868: 862 ~ 893 MHz, bước 1 MHz
+    Band868,
+    /// E32-915: 900 ~ 931 MHz, bước 1 MHz
+    Band915,
+}
+
+impl Band {
+    pub fn base_khz(&self, variant: ModuleVariant) -> u32 {
+        match (variant, self) {
+            (_, Band::Band170) => 160_000,
+            (ModuleVariant::E32, Band::Band433) => 410_000,
+            (ModuleVariant::E32, Band::Band868) => 862_000,
+            (ModuleVariant::E32, Band::Band915) => 900_000,
+            (_, Band::Band433) => 410_125,
+            (_, Band::Band868 | Band::Band915) => 850_125,
+        }
+    }
+
+    pub fn spacing_khz(&self) -> u32 {
+        match self {
+            Band::Band170 => 500,
+            _ => 1_000,
+        }
+    }
+
+    /// Kênh lớn nhất module chấp nhận. E22/E220 không có bản 170, dùng như E32.
+    pub fn max_channel(&self, variant: ModuleVariant) -> u8 {
+        match (variant, self) {
+            (_, Band::Band170) => 0x1B,
+            (ModuleVariant::E32, _) => 0x1F,
+            (_, Band::Band433) => 83,
+            (_, Band::Band868 | Band::Band915) => 80,
+        }
+    }
+
+    pub fn is_valid_channel(&self, variant: ModuleVariant, channel: u8) -> bool {
+        channel <= self.max_channel(variant)
+    }
+
+    /// Tần số sóng mang của `channel`, `None` nếu kênh nằm ngoài dải
+    pub fn frequency_khz(&self, variant: ModuleVariant, channel: u8) -> Option<u32> {
+        self.is_valid_channel(variant, channel)
+            .then(|| self.base_khz(variant) + channel as u32 * self.spacing_khz())
+    }
+
+    /// Byte thứ hai trả lời C3 C3 C3 của E32: mã tần số của module.
+    /// Chỉ phụ thuộc dải: E22/E220 không có lệnh C3, trả `FF FF FF` như lệnh thanh ghi sai.
+    pub fn model_byte(&self) -> u8 {
+        match self {
+            Band::Band170 => 0x3A,
+            Band::Band433 => 0x32,
+            Band::Band868 => 0x45,
+            Band::Band915 => 0x44,
+        }
+    }
+}
@@ -0,0 +1,532 @@
+//! Máy trạng thái của bridge PC ↔ E32 ↔ STM32, không phụ thuộc phần cứng
+
+use std::collections::VecDeque;
+
+use super::aux::*;
+use super::buffer::*;
+use super::command_parser::*;
+use super::e32_module::*;
+use super::error::E32Error;
+use super::fault::*;
+use super::framer::*;
+use super::hal::*;
+use super::link::*;
+use super::link_budget::*;
+
+pub const BUFF_SIZE: usize = 256;
+/// Baud lúc khởi động, trước khi có lệnh cấu hình nào
+pub const PC_BAUDRATE: u32 = 19200;
+/// UART1 theo mặc định 9600 8N1 của E32, `Bridge::new` đổi ngay sang tham số đã lưu
+pub const MCU_BAUDRATE: u32 = 9600;
+/// Flow control: dữ liệu chờ giao cho STM32 vượt ngưỡng này thì ngừng đọc PC,
+/// xuống dưới ngưỡng thấp thì đọc lại
+const FLOW_HIGH_WATER: usize = BUFF_SIZE * 3 / 4;
+const FLOW_LOW_WATER: usize = BUFF_SIZE / 4;
+
+/// Bộ đếm một chiều: byte bị bỏ ở hàng đợi nhận của UART (task đọc không
+/// theo kịp) và bộ đếm của buffer trong bridge
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PathStats {
+    pub rx_dropped: u64,
+    pub buffer: BufferStats,
+}
+
+pub struct Bridge<P, S, M, A, C> {
+    pc: P,  // UART0: PC ↔ ESP32
+    mcu: S, // UART1: ESP32 ↔ STM32
+    mode_pins: M,
+    aux: A,
+    aux_model: AuxModel,
+    clock: C,
+    e32: E32Module, // giữ cả chế độ hiện tại của module
+    parser: CommandParser, // ghép lệnh cấu hình ở chế độ Sleep
+    mode_effective_us: u64,
+    lower_buffer: Buffer<BUFF_SIZE>, // STM32 → PC
+    upper_buffer: Buffer<BUFF_SIZE>, // PC → STM32
+    // OverflowPolicy::Block: thời điểm buffer bắt đầu đầy
+    lower_full_since: Option<u64>,
+    upper_full_since: Option<u64>,
+    // byte task đọc UART đã bỏ trước khi tới buffer
+    pc_rx_dropped: u64,
+    mcu_rx_dropped: u64,
+    // ghi log bộ đếm mỗi khoảng này, None là tắt
+    stats_interval_us: Option<u64>,
+    next_stats_us: u64,
+    flow_control: bool,
+    pc_throttled: bool, // đang ngừng đọc PC, byte dồn lại ở UART0 và RTS chặn PC
+    pc_framer: IdleFramer,  // khung PC → STM32
+    mcu_framer: IdleFramer, // khung STM32 → PC
+    link: LinkModel,
+    faults: FaultInjector,
+    placement: Placement,
+    peer_placement: Placement, // bên phát phía PC
+    tx_air: VecDeque<InFlight>, // STM32 → không trung → PC
+    rx_air: VecDeque<InFlight>, // PC → không trung → STM32
+    // PowerSaving: thời điểm module thức dậy nghe lần tiếp theo
+    next_wake_us: u64,
+    // thời điểm xuất dữ liệu nhận ra STM32, sau khi AUX đã LOW đủ RX_NOTIFY_US
+    rx_deliver_us: Option<u64>,
+}
+
+impl<P, S, M, A, C> Bridge<P, S, M, A, C>
+where
+    P: SerialPort,
+    S: SerialPort,
+    M: ModePins,
+    A: AuxPin,
+    C: Clock,
+{
+    pub fn new(pc: P, mcu: S, mode_pins: M, mut aux: A, clock: C, e32: E32Module) -> anyhow::Result<Self> {
+        let now = clock.now_us();
+        // tự kiểm tra sau khi bật nguồn: AUX LOW
+        let mut aux_model = AuxModel::new();
+        aux_model.hold_low(now, SELF_CHECK_US);
+        aux_model.update(&mut aux, now, false)?;
+        let mut bridge = Self {
+            pc,
+            mcu,
+            mode_pins,
+            aux,
+            aux_model,
+            clock,
+            parser: CommandParser::new(e32.variant()),
+            e32,
+            mode_effective_us: now + SELF_CHECK_US,
+            lower_buffer: Buffer::new(),
+            upper_buffer: Buffer::new(),
+            lower_full_since: None,
+            upper_full_since: None,
+            pc_rx_dropped: 0,
+            mcu_rx_dropped: 0,
+            stats_interval_us: None,
+            next_stats_us: 0,
+            flow_control: false,
+            pc_throttled: false,
+            pc_framer: IdleFramer::new(PC_BAUDRATE, Parity::None),
+            mcu_framer: IdleFramer::new(MCU_BAUDRATE, Parity::None),
+            link: LinkModel::new(),
+            faults: FaultInjector::new(FaultConfig::none()),
+            placement: Placement::default(),
+            peer_placement: Placement::default(),
+            tx_air: VecDeque::new(),
+            rx_air: VecDeque::new(),
+            next_wake_us: now,
+            rx_deliver_us: None,
+        };
+        // UART1 chạy theo tham số hiệu lực (mặc định hoặc C0 đã lưu) ngay từ
+        // đầu, bất kể cổng được mở ở baud nào, như module thật sau khi khởi động lại
+        bridge.configure_mcu_uart()?;
+        Ok(bridge)
+    }
+
+    // các accessor chỉ dùng để kiểm tra trạng thái trong test trên host
+    #[allow(dead_code)]
+    pub fn state(&self) -> E32State {
+        self.e32.state()
+    }
+
+    #[allow(dead_code)]
+    pub fn e32(&self) -> &E32Module {
+        &self.e32
+    }
+
+    #[allow(dead_code)]
+    pub fn aux_is_high(&self) -> bool {
+        self.aux_model.is_high()
+    }
+
+    /// Chính sách khi buffer đầy, áp dụng cho cả hai chiều
+    pub fn set_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.lower_buffer.set_policy(policy);
+        self.upper_buffer.set_policy(policy);
+    }
+
+    /// Số thời gian ký tự lặng để coi là hết gói, cho cả hai UART
+    pub fn set_idle_chars(&mut self, idle_chars: u64) {
+        self.pc_framer.set_idle_chars(idle_chars);
+        self.mcu_framer.set_idle_chars(idle_chars);
+    }
+
+    /// Bật flow control phía PC: gần đầy thì ngừng đọc UART0 thay vì bỏ byte.
+    /// Cần RTS/CTS trên UART0 để PC thực sự dừng gửi.
+    pub fn set_flow_control(&mut self, enabled: bool) {
+        self.flow_control = enabled;
+        self.pc_throttled = false;
+    }
+
+    /// Đang ngừng đọc PC vì dữ liệu chờ giao cho STM32 đã gần đầy
+    #[allow(dead_code)]
+    pub fn pc_throttled(&self) -> bool {
+        self.pc_throttled
+    }
+
+    /// Bộ đếm chiều STM32 → PC
+    pub fn lower_stats(&self) -> PathStats {
+        PathStats { rx_dropped: self.mcu_rx_dropped, buffer: *self.lower_buffer.stats() }
+    }
+
+    /// Bộ đếm chiều PC → STM32
+    pub fn upper_stats(&self) -> PathStats {
+        PathStats { rx_dropped: self.pc_rx_dropped, buffer: *self.upper_buffer.stats() }
+    }
+
+    /// Ghi log bộ đếm hai chiều mỗi `interval_us`, None để tắt
+    pub fn set_stats_interval(&mut self, interval_us: Option<u64>) {
+        self.stats_interval_us = interval_us;
+        self.next_stats_us = self.clock.now_us() + interval_us.unwrap_or(0);
+    }
+
+    /// Bật tiêm lỗi trên chiều nhận (PC → không trung → STM32)
+    pub fn set_faults(&mut self, config: FaultConfig) {
+        self.faults = FaultInjector::new(config);
+    }
+
+    /// Đặt vị trí của module và của bên phát phía PC.
+    /// Bên phát được coi là dùng cùng công suất phát với module.
+    pub fn set_placement(&mut self, placement: Placement, peer_placement: Placement) {
+        self.placement = placement;
+        self.peer_placement = peer_placement;
+    }
+
+    /// Lỗi UART (parity, nhiễu...) chỉ làm mất byte lỗi, bridge vẫn chạy tiếp.
+    /// `idle` chạy sau mỗi vòng, ví dụ nhường CPU khi các cổng đọc không chặn.
+    pub fn run(&mut self, mut idle: impl FnMut()) -> anyhow::Result<()> {
+        loop {
+            if let Err(e) = self.poll() {
+                match e.downcast_ref::<E32Error>() {
+                    Some(E32Error::UartFault(_)) => log::warn!("bridge: {e}"),
+                    _ => return Err(e),
+                }
+            }
+            idle();
+        }
+    }
+
+    /// Một vòng lặp của bridge: đọc M0/M1 rồi xử lý theo chế độ
+    pub fn poll(&mut self) -> anyhow::Result<()> {
+        let requested = match (self.mode_pins.m0_is_high(), self.mode_pins.m1_is_high()) {
+            (false, false) => E32State::Normal,
+            (true, false) => E32State::WakeUp,
+            (false, true) => E32State::PowerSaving,
+            (true, true) => E32State::Sleep,
+        };
+        let now = self.clock.now_us();
+        self.pc_rx_dropped += self.pc.take_rx_dropped();
+        self.mcu_rx_dropped += self.mcu.take_rx_dropped();
+        if let Some(interval) = self.stats_interval_us.filter(|_| now >= self.next_stats_us) {
+            self.next_stats_us = now + interval;
+            log::info!("bridge: STM32 → PC {:?}", self.lower_stats());
+            log::info!("bridge: PC → STM32 {:?}", self.upper_stats());
+        }
+
+        // Chỉ chuyển chế độ khi đã phát hết bộ đệm TX và không bận
+        if requested != self.e32.state() {
+            let tx_done = (self.lower_buffer.available() == 0 || self.e32.state() == E32State::PowerSaving)
+                && self.tx_air.is_empty();
+            if tx_done && !self.aux_model.is_busy(now) {
+                self.switch_mode(requested, now);
+            }
+        }
+
+        if now >= self.mode_effective_us {
+            match self.e32.state() {
+                E32State::Normal | E32State::WakeUp | E32State::PowerSaving => self.handle_transparent()?,
+                E32State::Sleep => self.handle_config()?,
+            }
+        }
+        self.update_aux()
+    }
+
+    fn switch_mode(&mut self, next: E32State, now: u64) {
+        self.e32.set_state(next);
+        self.parser.clear();
+        // khung dở dang của chế độ cũ không được phát ở chế độ mới. Chỉ đổi
+        // chế độ khi lower_buffer đã phát hết; phần đang nhận từ không trung
+        // trong upper_buffer bị bỏ cùng khung của nó
+        self.pc_framer.clear();
+        self.mcu_framer.clear();
+        if !self.upper_buffer.is_empty() {
+            log::debug!("bridge: mode switch dropped {} received bytes", self.upper_buffer.available());
+            self.upper_buffer.clear();
+        }
+        self.rx_deliver_us = None;
+        self.aux_model.hold_low(now, MODE_SWITCH_US);
+        self.mode_effective_us = now + MODE_SWITCH_US + MODE_SETTLE_US;
+        self.next_wake_us = self.mode_effective_us + self.wake_up_time_us();
+    }
+
+    fn update_aux(&mut self) -> anyhow::Result<()> {
+        let now = self.clock.now_us();
+        let buffer_busy = self.lower_buffer.available() > 0 || !self.tx_air.is_empty() || self.rx_deliver_us.is_some();
+        self.aux_model.update(&mut self.aux, now, buffer_busy)
+    }
+
+    fn wake_up_time_us(&self) -> u64 {
+        self.e32.wake_up_ms() * 1000
+    }
+
+    fn read_port<T: SerialPort>(port: &mut T, buf: &mut [u8]) -> Result<usize, E32Error> {
+        port.read(buf).map_err(|e| E32Error::UartFault(e.to_string()))
+    }
+
+    /// Đọc các byte tới cùng lúc và thời điểm nhận, `now` nếu cổng không ghi lại
+    fn read_port_timed<T: SerialPort>(port: &mut T, buf: &mut [u8], now: u64) -> Result<(usize, u64), E32Error> {
+        let (n, at_us) = port.read_timed(buf).map_err(|e| E32Error::UartFault(e.to_string()))?;
+        Ok((n, at_us.unwrap_or(now)))
+    }
+
+    /// Đọc từ `port` vào `buffer` theo chính sách tràn của buffer.
+    /// Block: chỉ đọc vừa chỗ trống, phần thừa nằm lại trong UART; buffer
+    /// đầy quá `timeout_us` thì đọc tiếp và bỏ byte như Reject.
+    /// Flow control: không bao giờ đọc quá chỗ trống.
+    /// Mỗi lần đọc báo cho `framer` theo thời điểm tới của byte.
+    fn fill_buffer<T: SerialPort>(
+        port: &mut T,
+        buffer: &mut Buffer<BUFF_SIZE>,
+        framer: &mut IdleFramer,
+        full_since: &mut Option<u64>,
+        now: u64,
+        flow_control: bool,
+    ) -> Result<(), E32Error> {
+        let mut buf = [0u8; BUFF_SIZE];
+        let limit = match buffer.policy() {
+            _ if flow_control => buffer.free(),
+            OverflowPolicy::Block { timeout_us } if buffer.is_full() => {
+                let since = *full_since.get_or_insert(now);
+                if now - since < timeout_us {
+                    return Ok(());
+                }
+                BUFF_SIZE
+            }
+            OverflowPolicy::Block { .. } => {
+                *full_since = None;
+                buffer.free()
+            }
+            _ => BUFF_SIZE,
+        };
+        let mut total = 0;
+        while total < limit {
+            let (n, at_us) = Self::read_port_timed(port, &mut buf[..limit - total], now)?;
+            if n == 0 {
+                break;
+            }
+            framer.on_bytes(n, at_us);
+            let stored = buffer.push_slice(&buf[..n]);
+            if stored < n {
+                log::debug!("bridge: buffer full, dropped {} bytes", n - stored);
+            }
+            total += n;
+        }
+        Ok(())
+    }
+
+    fn handle_transparent(&mut self) -> anyhow::Result<()> {
+        let mut buf = [0u8; BUFF_SIZE];
+        let now = self.clock.now_us();
+
+        // Read from UART0 (PC) → upper buffer
+        if self.flow_control {
+            let pending = self.pending_to_mcu();
+            if pending >= FLOW_HIGH_WATER {
+                self.pc_throttled = true;
+            } else if pending <= FLOW_LOW_WATER {
+                self.pc_throttled = false;
+            }
+        }
+        if !self.pc_throttled {
+            let flow_control = self.flow_control;
+            Self::fill_buffer(&mut self.pc, &mut self.upper_buffer, &mut self.pc_framer, &mut self.upper_full_since, now, flow_control)?;
+        }
+        // Read from UART1 (STM32) → lower buffer
+        // PowerSaving: không phát, bỏ dữ liệu từ STM32
+        if self.e32.state() == E32State::PowerSaving {
+            Self::read_port(&mut self.mcu, &mut buf)?;
+        } else {
+            Self::fill_buffer(&mut self.mcu, &mut self.lower_buffer, &mut self.mcu_framer, &mut self.lower_full_since, now, false)?;
+        }
+
+        // Handle lower_buffer → không trung → UART0 (PowerSaving: không phát)
+        // Byte bị bỏ vì tràn vẫn được tính vào khung nên lấy ra không quá số byte đang có
+        let lower_frame = if self.e32.state() != E32State::PowerSaving { self.mcu_framer.poll(now) } else { None };
+        if let Some(len) = lower_frame.filter(|_| self.lower_buffer.available() > 0) {
+            // WakeUp: preamble kéo dài thêm wake-up time để đánh thức bên nhận
+            let extra_preamble_us = if self.e32.state() == E32State::WakeUp { self.wake_up_time_us() } else { 0 };
+            let n = self.lower_buffer.pop_into(&mut buf[..len.min(BUFF_SIZE)]);
+            let data = &buf[..n];
+            // Fixed: gói thiếu header ADDH ADDL CHAN thì module bỏ qua
+            let fixed = self.e32.config().fixed_transmission == FixedTransmission::PointToPoint;
+            if let Some(response) = self.e32.rssi_query(data) {
+                self.mcu.write(response.as_bytes())?;
+            } else if !fixed || self.fixed_target_in_band(data) {
+                self.send_air(now, data, extra_preamble_us, true);
+            }
+        }
+        while self.tx_air.front().is_some_and(|p| now >= p.done_us) {
+            if let Some(packet) = self.tx_air.pop_front() {
+                self.pc.write(&packet.data)?;
+            }
+        }
+
+        // Handle upper_buffer → không trung → UART1
+        // PowerSaving: bên nhận chỉ nghe ở mỗi chu kỳ wake-up, dữ liệu
+        // đến (từ bên phát ở chế độ WakeUp) được giao ở lần thức tiếp theo
+        let listening = if self.e32.state() == E32State::PowerSaving {
+            if now >= self.next_wake_us {
+                self.next_wake_us = now + self.wake_up_time_us();
+                true
+            } else {
+                false
+            }
+        } else {
+            true
+        };
+        // chưa nghe, hoặc STM32 bận tới mức dữ liệu trên không trung đã đủ một
+        // buffer, thì khung vẫn mở: byte đến sau dồn trong upper_buffer và
+        // chịu chính sách tràn của nó, có hay không có flow control
+        let upper_frame = if listening && self.in_air_to_mcu() < BUFF_SIZE { self.pc_framer.poll(now) } else { None };
+        if let Some(len) = upper_frame.filter(|_| self.upper_buffer.available() > 0) {
+            let n = self.upper_buffer.pop_into(&mut buf[..len.min(BUFF_SIZE)]);
+            let data = &buf[..n];
+            // Fixed: PC gửi kèm ADDH ADDL CHAN, chỉ giao phần dữ liệu cho node khớp địa chỉ và kênh
+            if self.e32.config().fixed_transmission == FixedTransmission::PointToPoint {
+                if let Some((target, payload)) = AirTarget::split_fixed(data) {
+                    if target.accepts(self.e32.address(), self.e32.channel()) {
+                        self.send_air(now, payload, 0, false);
+                    }
+                }
+            } else {
+                self.send_air(now, data, 0, false);
+            }
+        }
+        if self.rx_deliver_us.is_none() && self.rx_air.front().is_some_and(|p| now >= p.done_us) {
+            self.rx_deliver_us = Some(now + RX_NOTIFY_US);
+        }
+        // STM32 báo bận: giữ gói lại, AUX vẫn LOW cho tới khi giao xong
+        if self.rx_deliver_us.is_some_and(|at| now >= at) && !self.mode_pins.mcu_busy() {
+            self.rx_deliver_us = None;
+            if let Some(mut packet) = self.rx_air.pop_front() {
+                if self.faults.apply(&mut packet.data) {
+                    // E22/E220: nối thêm byte RSSI nếu REG3 bật
+                    packet.data.extend(self.e32.rssi_byte());
+                    self.mcu.write(&packet.data)?;
+                }
+                self.e32.record_environment_rssi(NOISE_FLOOR_DBM);
+            }
+        }
+        Ok(())
+    }
+
+    /// Số byte PC → STM32 chưa giao: còn trong buffer hoặc đang trên không trung
+    fn pending_to_mcu(&self) -> usize {
+        self.upper_buffer.available() + self.in_air_to_mcu()
+    }
+
+    /// Số byte đã rời upper_buffer nhưng chưa giao cho STM32
+    fn in_air_to_mcu(&self) -> usize {
+        self.rx_air.iter().map(|p| p.data.len()).sum()
+    }
+
+    /// Fixed: gói phải có header ADDH ADDL CHAN và kênh đích nằm trong dải tần của module
+    fn fixed_target_in_band(&self, frame: &[u8]) -> bool {
+        AirTarget::split_fixed(frame).is_some_and(|(target, _)| self.e32.is_valid_channel(target.channel))
+    }
+
+    /// Đưa dữ liệu lên kênh, mỗi gói con tới nơi sau air time của nó
+    fn send_air(&mut self, now: u64, data: &[u8], extra_preamble_us: u64, outgoing: bool) {
+        // bán song công: gói tới lúc module đang phát thì va chạm, mất gói
+        if !outgoing && self.faults.config().collisions && !self.tx_air.is_empty() {
+            return;
+        }
+        // chiều phát đã xếp sau gói đang nhận (LinkModel dùng chung), tức là
+        // luôn chờ kênh trống. Không bật LBT thì module phát ngay, gói đang nhận mất
+        if outgoing && self.faults.config().collisions && !self.e32.lbt_enabled() {
+            self.rx_air.retain(|p| p.done_us <= now);
+        }
+        let config = *self.e32.config();
+        if !outgoing {
+            // kênh ngoài dải thì không nghe được gì, gói yếu hơn độ nhạy thu thì mất
+            let Some(frequency_khz) = self.e32.frequency_khz() else { return };
+            let rssi = self.placement.rssi_from(&self.peer_placement, config.transmission_power.as_dbm(), frequency_khz);
+            if rssi < config.air_data_rate.sensitivity_dbm() {
+                return;
+            }
+            self.e32.record_signal_rssi(rssi);
+            self.e32.record_environment_rssi(rssi);
+        }
+        let queue = if outgoing { &mut self.tx_air } else { &mut self.rx_air };
+        self.link.send(now, data, config.air_data_rate, config.fec, extra_preamble_us, queue);
+    }
+
+    /// Timeout của lệnh tính theo lúc byte tới (ghi bởi task đọc UART), không
+    /// theo lúc vòng lặp lấy ra: vòng lặp có thể chậm hơn timeout nhiều lần.
+    fn handle_config(&mut self) -> anyhow::Result<()> {
+        let mut buf = [0_u8; BUFF_SIZE];
+        let now = self.clock.now_us();
+        // Sleep: tắt phần thu sóng, dữ liệu từ PC (trên không trung) bị bỏ
+        // ngay thay vì dồn lại rồi giao khi về chế độ khác
+        loop {
+            let n = Self::read_port(&mut self.pc, &mut buf)?;
+            if n == 0 {
+                break;
+            }
+            log::debug!("bridge: sleeping, dropped {n} bytes from the air");
+        }
+        loop {
+            let (n, at_us) = Self::read_port_timed(&mut self.mcu, &mut buf, now)?;
+            if n == 0 {
+                break;
+            }
+            for (i, &byte) in buf[..n].iter().enumerate() {
+                let arrival_us = self.mcu_framer.arrival_us(at_us, i, n);
+                if let Err(e) = self.parser.expire(arrival_us) {
+                    log::debug!("E32: dropped partial command: {e}");
+                }
+                match self.parser.push(byte, arrival_us) {
+                    Ok(Some(command)) => self.execute_command(command.as_bytes(), now)?,
+                    Ok(None) => {}
+                    Err(e) => log::debug!("E32: rejected command: {e}"),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Đổi baud/parity của UART1 theo cấu hình mới, sau khi phản hồi đã phát hết
+    /// ở cấu hình cũ. Air data rate chỉ dùng trong `LinkModel`, không đụng tới
+    /// UART0 vì PC luôn nối ở `PC_BAUDRATE`.
+    fn apply_uart_config(&mut self, before: &E32Config) -> anyhow::Result<()> {
+        let config = *self.e32.config();
+        if config.uart_bps == before.uart_bps && config.uart_parity == before.uart_parity {
+            return Ok(());
+        }
+        self.configure_mcu_uart()
+    }
+
+    /// Đặt baud/parity UART1, thời gian lặng của khung và timeout lệnh theo cấu hình hiện tại
+    fn configure_mcu_uart(&mut self) -> anyhow::Result<()> {
+        let config = *self.e32.config();
+        self.mcu.flush()?;
+        self.mcu.set_baudrate(config.uart_bps.as_baudrate())?;
+        self.mcu.set_parity(config.uart_parity.as_parity())?;
+        self.mcu_framer.set_line(config.uart_bps.as_baudrate(), config.uart_parity.as_parity());
+        // ở baud cao vài ký tự ngắn hơn độ trễ của task đọc, giữ tối thiểu như mặc định
+        self.parser.set_timeout_us(self.mcu_framer.idle_us().max(DEFAULT_COMMAND_TIMEOUT_US));
+        Ok(())
+    }
+
+    fn execute_command(&mut self, command: &[u8], now: u64) -> anyhow::Result<()> {
+        let before = *self.e32.config();
+        // lệnh sai thì module im lặng, chỉ ghi log
+        match self.e32.input_command(command, command.len()) {
+            Ok(response) if !response.is_empty() => self.mcu.write(response.as_bytes())?,
+            Ok(_) => {}
+            Err(e) => log::debug!("E32: rejected command: {e}"),
+        }
+        self.apply_uart_config(&before)?;
+        let busy_us = if command == [HEAD_RESET; 3] { SELF_CHECK_US } else { CONFIG_BUSY_US };
+        self.aux_model.hold_low(now, busy_us);
+        // không nhận lệnh mới khi AUX còn LOW
+        self.mode_effective_us = self.mode_effective_us.max(now + busy_us);
+        Ok(())
+    }
+}
@@ -1,58 +1,208 @@
-pub struct Buffer {
-    data: Vec<u8>,
-    cap: usize,
+//! Hàng đợi vòng dung lượng cố định, không cần cấp phát động
+
+use core::iter::Chain;
+use core::ops::Range;
+use core::slice;
+
+/// Cách xử lý khi thêm vào buffer đã đầy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Bỏ phần tử mới
+    Reject,
+    /// Bỏ phần tử cũ nhất để lấy chỗ cho phần tử mới
+    OverwriteOldest,
+    /// Bên ghi chờ tới khi có chỗ, quá `timeout_us` thì bỏ như `Reject`.
+    /// Buffer không tự chờ được: bên ghi dùng `free()` để chỉ đọc vừa đủ
+    /// chỗ trống, phần còn lại nằm chờ ở nguồn (UART, hàng đợi SPSC).
+    Block { timeout_us: u64 },
+}
+
+/// Bộ đếm để chọn kích thước buffer theo số liệu thực tế
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BufferStats {
+    pub pushed: u64,
+    pub popped: u64,
+    /// Số phần tử bị bỏ vì đầy (mới với Reject/Block, cũ với OverwriteOldest)
+    pub dropped: u64,
+    /// Số phần tử nhiều nhất từng có cùng lúc
+    pub high_water: usize,
+}
+
+/// Hai đoạn chỉ số liền nhau của `len` ô, bắt đầu từ ô `start` trong vòng
+/// `capacity` ô (đoạn sau rỗng nếu không quay vòng). Cần `start < capacity`
+/// (hoặc cả hai bằng 0) và `len <= capacity`.
+pub fn ring_ranges(start: usize, len: usize, capacity: usize) -> (Range<usize>, Range<usize>) {
+    let head = len.min(capacity - start);
+    (start..start + head, 0..len - head)
+}
+
+pub struct RingBuffer<T, const N: usize> {
+    data: [T; N],
     first: usize,
-    last: usize,
+    last: usize, // vị trí ghi tiếp theo
     size: usize,
+    policy: OverflowPolicy,
+    stats: BufferStats,
 }
 
-impl Buffer {
-    pub fn new(capacity: usize) -> Self {
+/// Hàng đợi byte giữa các UART
+pub type Buffer<const N: usize> = RingBuffer<u8, N>;
+
+impl<T: Copy + Default, const N: usize> RingBuffer<T, N> {
+    pub fn new() -> Self {
         Self {
-            data: vec![0; capacity],
-            cap: capacity,
+            data: [T::default(); N],
             first: 0,
-            last: 0, // dùng last = 0 thay vì -1
+            last: 0,
             size: 0,
+            policy: OverflowPolicy::Reject,
+            stats: BufferStats::default(),
         }
     }
 
-    /// Thêm một byte vào cuối buffer
-    pub fn enqueue(&mut self, byte: u8) -> bool {
-        if self.size < self.cap {
-            self.data[self.last] = byte;
-            self.last = (self.last + 1) % self.cap;
-            self.size += 1;
-            true
+    /// Chỉ số sau khi cộng, quay về đầu mảng nếu vượt cuối
+    fn wrap(index: usize) -> usize {
+        if index >= N {
+            index - N
         } else {
-            false
+            index
         }
     }
 
-    /// Lấy một byte từ đầu buffer
-    pub fn dequeue(&mut self) -> Option<u8> {
-        if self.size > 0 {
-            let byte = self.data[self.first];
-            self.first = (self.first + 1) % self.cap;
-            self.size -= 1;
-            Some(byte)
-        } else {
-            None
-        }
+    pub fn with_policy(policy: OverflowPolicy) -> Self {
+        Self { policy, ..Self::new() }
     }
 
-    /// Lấy toàn bộ dữ liệu và xóa buffer
-    pub fn deallqueue(&mut self) -> Vec<u8> {
-        let mut result = Vec::with_capacity(self.size);
-        while let Some(b) = self.dequeue() {
-            result.push(b);
-        }
-        self.size = 0; // Reset size after deallqueue
-        result
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: OverflowPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn stats(&self) -> &BufferStats {
+        &self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = BufferStats { high_water: self.size, ..BufferStats::default() };
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
     }
 
     /// Số phần tử hiện có
     pub fn available(&self) -> usize {
         self.size
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.size == N
+    }
+
+    /// Số chỗ còn trống
+    pub fn free(&self) -> usize {
+        N - self.size
+    }
+
+    /// Thêm một phần tử vào cuối, trả về `false` nếu phần tử bị bỏ
+    pub fn enqueue(&mut self, item: T) -> bool {
+        self.push_slice(&[item]) == 1
+    }
+
+    /// Lấy một phần tử từ đầu
+    pub fn dequeue(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        let item = self.data[self.first];
+        self.first = Self::wrap(self.first + 1);
+        self.size -= 1;
+        self.stats.popped += 1;
+        Some(item)
+    }
+
+    /// Phần tử ở đầu, không lấy ra
+    pub fn peek(&self) -> Option<&T> {
+        (!self.is_empty()).then(|| &self.data[self.first])
+    }
+
+    /// Thêm nhiều phần tử theo chính sách tràn, trả về số phần tử đã thêm
+    pub fn push_slice(&mut self, items: &[T]) -> usize {
+        let items = if self.policy == OverflowPolicy::OverwriteOldest {
+            // chỉ N phần tử cuối còn lại được, bỏ bớt phần tử cũ cho đủ chỗ
+            let keep = &items[items.len().saturating_sub(N)..];
+            let evict = keep.len().saturating_sub(self.free());
+            self.first = Self::wrap(self.first + evict);
+            self.size -= evict;
+            self.stats.dropped += (items.len() - keep.len() + evict) as u64;
+            keep
+        } else {
+            items
+        };
+        let count = items.len().min(N - self.size);
+        self.stats.dropped += (items.len() - count) as u64;
+        // chép tới cuối mảng rồi quay vòng về đầu
+        let (head, tail) = ring_ranges(self.last, count, N);
+        let (items_head, items_tail) = items[..count].split_at(head.len());
+        self.data[head].copy_from_slice(items_head);
+        self.data[tail].copy_from_slice(items_tail);
+        self.last = Self::wrap(self.last + count);
+        self.size += count;
+        self.stats.pushed += count as u64;
+        self.stats.high_water = self.stats.high_water.max(self.size);
+        count
+    }
+
+    /// Lấy tối đa `out.len()` phần tử ra `out`, trả về số phần tử đã lấy
+    pub fn pop_into(&mut self, out: &mut [T]) -> usize {
+        let count = out.len().min(self.size);
+        let (head, tail) = ring_ranges(self.first, count, N);
+        let (out_head, out_tail) = out[..count].split_at_mut(head.len());
+        out_head.copy_from_slice(&self.data[head]);
+        out_tail.copy_from_slice(&self.data[tail]);
+        self.first = Self::wrap(self.first + count);
+        self.size -= count;
+        self.stats.popped += count as u64;
+        count
+    }
+
+    /// Dữ liệu theo thứ tự dưới dạng hai đoạn liền nhau (đoạn sau rỗng nếu chưa quay vòng)
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let (head, tail) = ring_ranges(self.first, self.size, N);
+        (&self.data[head], &self.data[tail])
+    }
+
+    /// Duyệt từ đầu tới cuối, không lấy ra
+    pub fn iter(&self) -> Chain<slice::Iter<'_, T>, slice::Iter<'_, T>> {
+        let (head, tail) = self.as_slices();
+        head.iter().chain(tail.iter())
+    }
+
+    pub fn clear(&mut self) {
+        self.first = 0;
+        self.last = 0;
+        self.size = 0;
+    }
+}
+
+impl<T: Copy + Default, const N: usize> Default for RingBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T: Copy + Default, const N: usize> IntoIterator for &'a RingBuffer<T, N> {
+    type Item = &'a T;
+    type IntoIter = Chain<slice::Iter<'a, T>, slice::Iter<'a, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
 }
@@ -0,0 +1,114 @@
+//! Ghép lệnh cấu hình từ từng byte nhận ở chế độ Sleep.
+//! Lệnh có thể tới trong nhiều lần đọc UART; khung dở dang quá lâu không
+//! có byte mới thì bị bỏ.
+//!
+//! - E32: `C0`/`C2` + 5 byte tham số, `C1 C1 C1`, `C3 C3 C3`, `C4 C4 C4`
+//! - E22/E220: `C0`/`C2 addr len data`, các lệnh khác 3 byte `head addr len`
+
+use super::e32_module::*;
+use super::error::E32Error;
+use super::register_map::{ModuleVariant, REGISTER_HEADER_SIZE};
+
+/// Mặc định: 3 ký tự ở 9600 bps không có byte mới thì bỏ khung dở dang
+pub const DEFAULT_COMMAND_TIMEOUT_US: u64 = 3 * 1_042;
+
+const SHORT_COMMAND_SIZE: usize = 3;
+
+/// Một lệnh hoàn chỉnh, đưa thẳng cho `E32Module::input_command`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandFrame {
+    data: [u8; MAX_COMMAND_SIZE],
+    len: usize,
+}
+
+impl CommandFrame {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data[..self.len]
+    }
+}
+
+pub struct CommandParser {
+    register_commands: bool, // E22/E220
+    frame: CommandFrame,
+    last_byte_us: u64,
+    timeout_us: u64,
+}
+
+impl CommandParser {
+    pub fn new(variant: ModuleVariant) -> Self {
+        Self {
+            register_commands: variant != ModuleVariant::E32,
+            frame: CommandFrame { data: [0u8; MAX_COMMAND_SIZE], len: 0 },
+            last_byte_us: 0,
+            timeout_us: DEFAULT_COMMAND_TIMEOUT_US,
+        }
+    }
+
+    /// Thời gian tối đa giữa hai byte của cùng một lệnh
+    pub fn set_timeout_us(&mut self, timeout_us: u64) {
+        self.timeout_us = timeout_us;
+    }
+
+    /// Bỏ khung dở dang (đổi chế độ, reset)
+    pub fn clear(&mut self) {
+        self.frame.len = 0;
+    }
+
+    /// Bỏ khung dở dang nếu đã quá `timeout_us` không có byte mới
+    pub fn expire(&mut self, now_us: u64) -> Result<(), E32Error> {
+        if self.frame.len == 0 || now_us.saturating_sub(self.last_byte_us) <= self.timeout_us {
+            return Ok(());
+        }
+        let error = E32Error::BadLength { expected: self.expected_len().unwrap_or(0), actual: self.frame.len };
+        self.clear();
+        Err(error)
+    }
+
+    /// Nhận một byte. Trả về lệnh khi đủ byte, lỗi nếu byte đầu không phải
+    /// header hợp lệ hoặc lệnh dài quá bộ đệm. Khung cũ quá hạn bị bỏ trước.
+    pub fn push(&mut self, byte: u8, now_us: u64) -> Result<Option<CommandFrame>, E32Error> {
+        let _ = self.expire(now_us);
+        self.last_byte_us = now_us;
+
+        if self.frame.len == 0 && !self.register_commands && !Self::is_e32_header(byte) {
+            return Err(E32Error::BadHeader(byte));
+        }
+        self.frame.data[self.frame.len] = byte;
+        self.frame.len += 1;
+
+        match self.expected_len() {
+            Some(expected) if expected > MAX_COMMAND_SIZE => {
+                self.clear();
+                Err(E32Error::BadLength { expected, actual: MAX_COMMAND_SIZE })
+            }
+            Some(expected) if self.frame.len >= expected => {
+                let frame = self.frame;
+                self.clear();
+                Ok(Some(frame))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn is_e32_header(byte: u8) -> bool {
+        matches!(
+            byte,
+            HEAD_WRITE_SAVED | HEAD_READ_PARAMS | HEAD_WRITE_TEMPORARY | HEAD_READ_VERSION | HEAD_RESET
+        )
+    }
+
+    /// Độ dài lệnh suy từ các byte đã có, `None` nếu chưa đủ để biết
+    fn expected_len(&self) -> Option<usize> {
+        let head = *self.frame.as_bytes().first()?;
+        let is_write = head == HEAD_WRITE_SAVED || head == HEAD_WRITE_TEMPORARY;
+        if !self.register_commands {
+            return Some(if is_write { CONF_SIZE } else { SHORT_COMMAND_SIZE });
+        }
+        if !is_write {
+            return Some(REGISTER_HEADER_SIZE);
+        }
+        // C0/C2 addr len data: byte thứ 3 là số thanh ghi cần ghi
+        let len = *self.frame.as_bytes().get(2)?;
+        Some(REGISTER_HEADER_SIZE + len as usize)
+    }
+}
@@ -1,3 +1,12 @@
+use core::fmt;
+
+use super::band::Band;
+use super::error::E32Error;
+use super::hal::Parity;
+use super::link_budget::NOISE_FLOOR_DBM;
+use super::param_store::ParamStore;
+use super::register_map::*;
+
 #[repr(usize)] // bảo đảm giá trị enum = số nguyên
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum ParamsOrder {
@@ -22,14 +31,48 @@ pub enum UartBps {
     Bps115200 = 0b111,
 }
 
-// #[repr(u8)]
-// #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-// pub enum As32UartParity {
-//     Mode8N1 = 0b00,
-//     Mode8O1 = 0b01,
-//     Mode8E1 = 0b10,
-//     Mode8N1_2 = 0b11, // đặt tên khác vì trùng với 0b00
-// }
+impl UartBps {
+    pub fn as_baudrate(&self) -> u32 {
+        match self {
+            UartBps::Bps1200 => 1200,
+            UartBps::Bps2400 => 2400,
+            UartBps::Bps4800 => 4800,
+            UartBps::Bps9600 => 9600,
+            UartBps::Bps19200 => 19200,
+            UartBps::Bps38400 => 38400,
+            UartBps::Bps57600 => 57600,
+            UartBps::Bps115200 => 115200,
+        }
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum As32UartParity {
+    Mode8N1 = 0b00,
+    Mode8O1 = 0b01,
+    Mode8E1 = 0b10,
+    Mode8N1_2 = 0b11, // đặt tên khác vì trùng với 0b00
+}
+
+impl As32UartParity {
+    pub fn from_bits(bits: u8) -> Self {
+        match bits & 0b11 {
+            0b00 => As32UartParity::Mode8N1,
+            0b01 => As32UartParity::Mode8O1,
+            0b10 => As32UartParity::Mode8E1,
+            _ => As32UartParity::Mode8N1_2,
+        }
+    }
+
+    pub fn as_parity(&self) -> Parity {
+        match self {
+            As32UartParity::Mode8O1 => Parity::Odd,
+            As32UartParity::Mode8E1 => Parity::Even,
+            As32UartParity::Mode8N1 | As32UartParity::Mode8N1_2 => Parity::None,
+        }
+    }
+}
 
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -40,64 +83,114 @@ pub enum AirDataRate {
     Rate4800 = 0b011,
     Rate9600 = 0b100,
     Rate19200 = 0b101,
+    Rate38400 = 0b110, // chỉ có trên E22/E220
+    Rate62500 = 0b111, // chỉ có trên E22/E220
+}
+
+impl AirDataRate {
+    pub fn as_bps(&self) -> u32 {
+        match self {
+            AirDataRate::Rate300 => 300,
+            AirDataRate::Rate1200 => 1200,
+            AirDataRate::Rate2400 => 2400,
+            AirDataRate::Rate4800 => 4800,
+            AirDataRate::Rate9600 => 9600,
+            AirDataRate::Rate19200 => 19200,
+            AirDataRate::Rate38400 => 38400,
+            AirDataRate::Rate62500 => 62500,
+        }
+    }
+
+    /// Độ nhạy thu (dBm), gói yếu hơn mức này bị mất
+    pub fn sensitivity_dbm(&self) -> f64 {
+        match self {
+            AirDataRate::Rate300 => -138.0,
+            AirDataRate::Rate1200 => -135.0,
+            AirDataRate::Rate2400 => -130.0,
+            AirDataRate::Rate4800 => -127.0,
+            AirDataRate::Rate9600 => -124.0,
+            AirDataRate::Rate19200 => -121.0,
+            AirDataRate::Rate38400 => -118.0,
+            AirDataRate::Rate62500 => -116.0,
+        }
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransmissionPower {
+    Power20 = 0b00,
+    Power17 = 0b01,
+    Power14 = 0b10,
+    Power10 = 0b11,
+}
+
+impl TransmissionPower {
+    pub fn as_dbm(&self) -> f64 {
+        match self {
+            TransmissionPower::Power20 => 20.0,
+            TransmissionPower::Power17 => 17.0,
+            TransmissionPower::Power14 => 14.0,
+            TransmissionPower::Power10 => 10.0,
+        }
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WirelessWakeUpTime {
+    WakeUp250 = 0b000,
+    WakeUp500 = 0b001,
+    WakeUp750 = 0b010,
+    WakeUp1000 = 0b011,
+    WakeUp1250 = 0b100,
+    WakeUp1500 = 0b101,
+    WakeUp1750 = 0b110,
+    WakeUp2000 = 0b111,
+}
+
+impl WirelessWakeUpTime {
+    /// 250 ms cho mỗi bước, từ 250 ms đến 2000 ms
+    pub fn as_millis(&self) -> u64 {
+        (*self as u64 + 1) * 250
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixedTransmission {
+    Transparent = 0,
+    PointToPoint = 1,
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoDriveMode {
+    OpenCollector = 0b0,
+    PushPull = 0b1,
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForwardErrorCorrection {
+    Off = 0,
+    On = 1,
+}
+
+/// Thanh ghi RSSI đọc bằng lệnh `C0 C1 C2 C3 <reg> <len>` ở chế độ Normal
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RssiRegister {
+    Environment = 0x00, // nhiễu nền hiện tại
+    Signal = 0x01,      // gói nhận gần nhất
 }
 
-// #[repr(u8)]
-// #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-// pub enum TransmissionPower {
-//     Power20 = 0b00,
-//     Power17 = 0b01,
-//     Power14 = 0b10,
-//     Power10 = 0b11,
-// }
-
-// #[repr(u8)]
-// #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-// pub enum WirelessWakeUpTime {
-//     WakeUp250 = 0b000,
-//     WakeUp500 = 0b001,
-//     WakeUp750 = 0b010,
-//     WakeUp1000 = 0b011,
-//     WakeUp1250 = 0b100,
-//     WakeUp1500 = 0b101,
-//     WakeUp1750 = 0b110,
-//     WakeUp2000 = 0b111,
-// }
-
-// #[repr(u8)]
-// #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-// pub enum FixedTransmission {
-//     Transparent = 0,
-//     PointToPoint = 1,
-// }
-
-// #[repr(u8)]
-// #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-// pub enum IoDriveMode {
-//     OpenCollector = 0b0,
-//     PushPull = 0b1,
-// }
-
-// #[repr(u8)]
-// #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-// pub enum NormalCommand {
-//     ReadConfig = 0,
-//     ReadVersion = 1,
-//     ResetModule = 2,
-//     ReadVoltage = 3,
-//     RestoreDefaults = 4,
-//     Handshake = 5,
-//     ReadSoftwareVersion = 6,
-//     ReadRssiSignal = 7,
-//     ReadRssiEnvironment = 8,
-// }
-
-// #[repr(u8)]
-// #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-// pub enum ConfigCommand {
-//     WriteSaved = 0,
-//     WriteTemporary = 1,
-// }
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigCommand {
+    WriteSaved = 0,
+    WriteTemporary = 1,
+}
 
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -109,34 +202,124 @@ pub enum E32State {
 }
 
 pub const CONF_SIZE: usize = 6;
+pub const VERSION_SIZE: usize = 4;
 
-pub struct E32Module {
-    params_list: [u8; CONF_SIZE],
-    pub air_data_rate: AirDataRate,
+pub const HEAD_WRITE_SAVED: u8 = 0xC0;
+pub const HEAD_READ_PARAMS: u8 = 0xC1;
+pub const HEAD_WRITE_TEMPORARY: u8 = 0xC2;
+pub const HEAD_READ_VERSION: u8 = 0xC3;
+pub const HEAD_RESET: u8 = 0xC4;
+
+pub const RSSI_COMMAND_PREFIX: [u8; 4] = [0xC0, 0xC1, 0xC2, 0xC3];
+pub const RSSI_COMMAND_SIZE: usize = 6;
+
+const FIRMWARE_VERSION: u8 = 0x27;
+const MODULE_FEATURES: u8 = 0x14;
+
+/// Lệnh/phản hồi dài nhất: ghi hoặc đọc toàn bộ 9 thanh ghi của E22 kèm 3 byte header
+pub const MAX_COMMAND_SIZE: usize = 12;
+const MAX_RESPONSE_SIZE: usize = MAX_COMMAND_SIZE;
+
+/// Chuỗi byte E32 gửi trả qua UART, giống hệt phần cứng
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct E32Response {
+    data: [u8; MAX_RESPONSE_SIZE],
+    len: usize,
+}
+
+impl E32Response {
+    /// Không có phản hồi (reset, lệnh không hợp lệ)
+    pub fn none() -> Self {
+        Self { data: [0u8; MAX_RESPONSE_SIZE], len: 0 }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut response = Self::none();
+        response.len = bytes.len().min(MAX_RESPONSE_SIZE);
+        response.data[..response.len].copy_from_slice(&bytes[..response.len]);
+        response
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data[..self.len]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Dạng hex dễ đọc để debug, ví dụ "C0 00 00 1A 17 44"
+impl fmt::Display for E32Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, b) in self.as_bytes().iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{:02X}", b)?;
+        }
+        Ok(())
+    }
+}
+
+/// Cấu hình 6 byte `HEAD ADDH ADDL SPED CHAN OPTION` ở dạng có kiểu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct E32Config {
+    /// C0 (lưu vào flash) hoặc C2 (ghi tạm)
+    pub command: ConfigCommand,
+    pub address: u16,
+    pub uart_parity: As32UartParity,
     pub uart_bps: UartBps,
+    pub air_data_rate: AirDataRate,
+    pub channel: u8,
+    pub fixed_transmission: FixedTransmission,
+    pub io_drive_mode: IoDriveMode,
+    pub wake_up_time: WirelessWakeUpTime,
+    pub fec: ForwardErrorCorrection,
+    pub transmission_power: TransmissionPower,
 }
 
-impl E32Module {
-    pub fn new() -> Self {
-        let mut params_list = [0u8; CONF_SIZE];
-        params_list[ParamsOrder::Head as usize]   = 0xC0;
-        params_list[ParamsOrder::Addh as usize]   = 0x00;
-        params_list[ParamsOrder::Addl as usize]   = 0x00;
-        params_list[ParamsOrder::Sped as usize]   = 0x1A;
-        params_list[ParamsOrder::Chan as usize]   = 0x17;
-        params_list[ParamsOrder::Option as usize] = 0x44;
-        let sped = params_list[ParamsOrder::Sped as usize];
+/// Mặc định xuất xưởng: C0 00 00 1A 17 44
+impl Default for E32Config {
+    fn default() -> Self {
+        Self {
+            command: ConfigCommand::WriteSaved,
+            address: 0x0000,
+            uart_parity: As32UartParity::Mode8N1,
+            uart_bps: UartBps::Bps9600,
+            air_data_rate: AirDataRate::Rate2400,
+            channel: 0x17,
+            fixed_transmission: FixedTransmission::Transparent,
+            io_drive_mode: IoDriveMode::PushPull,
+            wake_up_time: WirelessWakeUpTime::WakeUp250,
+            fec: ForwardErrorCorrection::On,
+            transmission_power: TransmissionPower::Power20,
+        }
+    }
+}
+
+impl TryFrom<[u8; CONF_SIZE]> for E32Config {
+    type Error = E32Error;
 
+    fn try_from(params: [u8; CONF_SIZE]) -> Result<Self, Self::Error> {
+        let command = match params[ParamsOrder::Head as usize] {
+            HEAD_WRITE_SAVED => ConfigCommand::WriteSaved,
+            HEAD_WRITE_TEMPORARY => ConfigCommand::WriteTemporary,
+            head => return Err(E32Error::BadHeader(head)),
+        };
+
+        // SPED: bit 7-6 parity, bit 5-3 UART baud, bit 2-0 air data rate
+        let sped = params[ParamsOrder::Sped as usize];
         let air_data_rate = match sped & 0b0000_0111 {
             0b000 => AirDataRate::Rate300,
             0b001 => AirDataRate::Rate1200,
             0b010 => AirDataRate::Rate2400,
             0b011 => AirDataRate::Rate4800,
             0b100 => AirDataRate::Rate9600,
-            0b101 | 0b110 | 0b111 => AirDataRate::Rate19200,
-            _ => AirDataRate::Rate2400, // default fallback
+            0b101 => AirDataRate::Rate19200,
+            // 0b110/0b111 để dành cho E22/E220, E32 không có
+            code => return Err(E32Error::ReservedValue { field: "air data rate", value: code }),
         };
-
         let uart_bps = match (sped >> 3) & 0b0000_0111 {
             0b000 => UartBps::Bps1200,
             0b001 => UartBps::Bps2400,
@@ -145,70 +328,373 @@ impl E32Module {
             0b100 => UartBps::Bps19200,
             0b101 => UartBps::Bps38400,
             0b110 => UartBps::Bps57600,
-            0b111 => UartBps::Bps115200,
-            _ => UartBps::Bps9600, // fallback
+            _ => UartBps::Bps115200,
         };
 
-        Self {
-            params_list,
-            air_data_rate,
+        // OPTION: bit 7 fixed transmission, bit 6 IO drive, bit 5-3 wake-up time,
+        // bit 2 FEC, bit 1-0 TX power
+        let option = params[ParamsOrder::Option as usize];
+        let wake_up_time = match (option >> 3) & 0b0000_0111 {
+            0b000 => WirelessWakeUpTime::WakeUp250,
+            0b001 => WirelessWakeUpTime::WakeUp500,
+            0b010 => WirelessWakeUpTime::WakeUp750,
+            0b011 => WirelessWakeUpTime::WakeUp1000,
+            0b100 => WirelessWakeUpTime::WakeUp1250,
+            0b101 => WirelessWakeUpTime::WakeUp1500,
+            0b110 => WirelessWakeUpTime::WakeUp1750,
+            _ => WirelessWakeUpTime::WakeUp2000,
+        };
+        let transmission_power = match option & 0b0000_0011 {
+            0b00 => TransmissionPower::Power20,
+            0b01 => TransmissionPower::Power17,
+            0b10 => TransmissionPower::Power14,
+            _ => TransmissionPower::Power10,
+        };
+
+        Ok(Self {
+            command,
+            address: u16::from_be_bytes([params[ParamsOrder::Addh as usize], params[ParamsOrder::Addl as usize]]),
+            uart_parity: As32UartParity::from_bits(sped >> 6),
             uart_bps,
+            air_data_rate,
+            channel: params[ParamsOrder::Chan as usize],
+            fixed_transmission: match (option >> 7) & 0b1 {
+                0b0 => FixedTransmission::Transparent,
+                _ => FixedTransmission::PointToPoint,
+            },
+            io_drive_mode: match (option >> 6) & 0b1 {
+                0b0 => IoDriveMode::OpenCollector,
+                _ => IoDriveMode::PushPull,
+            },
+            wake_up_time,
+            fec: match (option >> 2) & 0b1 {
+                0b0 => ForwardErrorCorrection::Off,
+                _ => ForwardErrorCorrection::On,
+            },
+            transmission_power,
+        })
+    }
+}
+
+impl From<E32Config> for [u8; CONF_SIZE] {
+    fn from(config: E32Config) -> Self {
+        let head = match config.command {
+            ConfigCommand::WriteSaved => HEAD_WRITE_SAVED,
+            ConfigCommand::WriteTemporary => HEAD_WRITE_TEMPORARY,
+        };
+        let [addh, addl] = config.address.to_be_bytes();
+        let sped = ((config.uart_parity as u8) << 6) | ((config.uart_bps as u8) << 3) | (config.air_data_rate as u8);
+        let option = ((config.fixed_transmission as u8) << 7)
+            | ((config.io_drive_mode as u8) << 6)
+            | ((config.wake_up_time as u8) << 3)
+            | ((config.fec as u8) << 2)
+            | (config.transmission_power as u8);
+        [head, addh, addl, sped, config.channel, option]
+    }
+}
+
+pub struct E32Module {
+    config: E32Config,
+    saved_config: E32Config, // tham số đã lưu vào flash
+    store: Option<Box<dyn ParamStore>>,
+    registers: Option<RegisterMap>, // E22/E220 dùng thanh ghi, đồng bộ sang `config`
+    band: Band,
+    state: E32State, // chế độ theo M0/M1
+    rssi_signal_dbm: Option<f64>,
+    rssi_environment_dbm: f64,
+}
+
+impl E32Module {
+    pub fn new() -> Self {
+        Self {
+            config: E32Config::default(),
+            saved_config: E32Config::default(),
+            store: None,
+            registers: None,
+            band: Band::Band433,
+            state: E32State::Normal,
+            rssi_signal_dbm: None,
+            rssi_environment_dbm: NOISE_FLOOR_DBM,
         }
     }
-    pub fn input_command(&mut self, command: &[u8], size: usize) -> String {
-        const CONF_SIZE: usize = 6;
-        let mut buffer = [0u8; CONF_SIZE];
 
-        if command.len() == CONF_SIZE && command[0] == 0xC0 {
-            self.set_params(command, size);
-            "OK".to_string()
-        } 
-        else if command.len() == 3 && command[0] == 0xC1 && command[1] == 0xC1 && command[2] == 0xC1 {
-            self.get_params(&mut buffer);
-            buffer.iter()
-                .map(|b| format!("{:02X}", b))
-                .collect::<Vec<_>>()
-                .join(" ")
-        } 
-        else {
-            "ERROR".to_string()
+    /// Khởi tạo từ tham số đã lưu trong `store`, dùng mặc định nếu chưa có.
+    /// Ảnh thanh ghi của E22/E220 được nạp ở `set_variant`.
+    pub fn with_store(mut store: Box<dyn ParamStore>) -> Self {
+        let mut module = Self::new();
+        match store.load() {
+            Ok(Some(params)) => match <[u8; CONF_SIZE]>::try_from(params.as_slice()).map(E32Config::try_from) {
+                Ok(Ok(config)) if config.command == ConfigCommand::WriteSaved => {
+                    module.saved_config = config;
+                    module.reset();
+                }
+                Ok(Ok(_)) => {}
+                // không phải 6 byte: ảnh thanh ghi, nạp ở `set_variant`
+                Err(_) => {}
+                Ok(Err(e)) => log::warn!("E32: ignoring saved params: {e}"),
+            },
+            Ok(None) => {}
+            Err(e) => log::warn!("E32: failed to load saved params: {e}"),
         }
+        module.store = Some(store);
+        module
+    }
+
+    /// Cấu hình hiện tại (gồm cả thay đổi tạm từ C2)
+    pub fn config(&self) -> &E32Config {
+        &self.config
     }
-    pub fn set_params(&mut self, params: &[u8], size: usize) {
-        if size != CONF_SIZE {
-            return;
+
+    /// Chọn dòng module cần giả lập, thanh ghi trở về giá trị mặc định của dòng đó
+    /// rồi nạp ảnh thanh ghi đã lưu nếu có
+    pub fn set_variant(&mut self, variant: ModuleVariant) {
+        self.registers = RegisterMap::new(variant);
+        if let (Some(registers), Some(store)) = (self.registers.as_mut(), self.store.as_mut()) {
+            match store.load() {
+                Ok(Some(image)) => {
+                    registers.restore(&image);
+                }
+                Ok(None) => {}
+                Err(e) => log::warn!("E32: failed to load saved registers: {e}"),
+            }
         }
-        for i in 0..size {
-            self.params_list[i] = params[i];
+        self.sync_registers();
+    }
+
+    pub fn variant(&self) -> ModuleVariant {
+        match &self.registers {
+            Some(registers) => registers.variant(),
+            None => ModuleVariant::E32,
         }
-        let sped = self.params_list[ParamsOrder::Sped as usize];
+    }
 
-        self.air_data_rate = match sped & 0b0000_0111 {
-            0b000 => AirDataRate::Rate300,
-            0b001 => AirDataRate::Rate1200,
-            0b010 => AirDataRate::Rate2400,
-            0b011 => AirDataRate::Rate4800,
-            0b100 => AirDataRate::Rate9600,
-            0b101 | 0b110 | 0b111 => AirDataRate::Rate19200,
-            _ => AirDataRate::Rate2400, // default fallback
-        };
+    /// Chọn dải tần (E32-170/433/868/915), mặc định là 433
+    pub fn set_band(&mut self, band: Band) {
+        self.band = band;
+    }
 
-        self.uart_bps = match (sped >> 3) & 0b0000_0111 {
-            0b000 => UartBps::Bps1200,
-            0b001 => UartBps::Bps2400,
-            0b010 => UartBps::Bps4800,
-            0b011 => UartBps::Bps9600,
-            0b100 => UartBps::Bps19200,
-            0b101 => UartBps::Bps38400,
-            0b110 => UartBps::Bps57600,
-            0b111 => UartBps::Bps115200,
-            _ => UartBps::Bps9600, // fallback
+    /// Tần số sóng mang hiện tại, `None` nếu kênh nằm ngoài dải
+    pub fn frequency_khz(&self) -> Option<u32> {
+        self.channel_frequency_khz(self.channel())
+    }
+
+    /// Tần số sóng mang của `channel` theo dải và dòng module
+    pub fn channel_frequency_khz(&self, channel: u8) -> Option<u32> {
+        self.band.frequency_khz(self.variant(), channel)
+    }
+
+    pub fn is_valid_channel(&self, channel: u8) -> bool {
+        self.band.is_valid_channel(self.variant(), channel)
+    }
+
+    pub fn state(&self) -> E32State {
+        self.state
+    }
+
+    /// Đổi chế độ khi M0/M1 thay đổi
+    pub fn set_state(&mut self, state: E32State) {
+        self.state = state;
+    }
+
+    /// Xử lý một lệnh cấu hình, chỉ nhận ở chế độ Sleep (M0 = M1 = 1).
+    /// Lỗi thì module không trả lời gì qua UART, giống phần cứng,
+    /// còn firmware/test biết được nguyên nhân qua `E32Error`.
+    pub fn input_command(&mut self, command: &[u8], size: usize) -> Result<E32Response, E32Error> {
+        if self.state != E32State::Sleep {
+            return Err(E32Error::WrongMode(self.state));
+        }
+        let command = &command[..size.min(command.len())];
+        if let Some(registers) = self.registers.as_mut() {
+            let response = registers.input_command(command);
+            // C0 hợp lệ thì lưu cả ảnh thanh ghi, như C0 của E32
+            if command.first() == Some(&HEAD_WRITE_SAVED) && response.as_bytes() != WRONG_FORMAT_RESPONSE {
+                if let Some(store) = self.store.as_mut() {
+                    if let Err(e) = store.save(registers.saved()) {
+                        log::warn!("E32: failed to save registers: {e}");
+                    }
+                }
+            }
+            self.sync_registers();
+            return Ok(response);
+        }
+        let mut buffer = [0u8; CONF_SIZE];
+
+        let response: &[u8] = match command {
+            [HEAD_WRITE_SAVED | HEAD_WRITE_TEMPORARY, ..] => {
+                let params = <[u8; CONF_SIZE]>::try_from(command)
+                    .map_err(|_| E32Error::BadLength { expected: CONF_SIZE, actual: command.len() })?;
+                let config = E32Config::try_from(params)?;
+                // kênh ngoài dải của module thì bỏ qua cả lệnh
+                if !self.is_valid_channel(config.channel) {
+                    return Err(E32Error::ReservedValue { field: "channel", value: config.channel });
+                }
+                self.write_params(config);
+                // C0 trả lời C0, C2 trả lời C2
+                buffer = E32Config { command: config.command, ..self.config }.into();
+                &buffer
+            }
+            [HEAD_READ_PARAMS, HEAD_READ_PARAMS, HEAD_READ_PARAMS] => {
+                self.get_params(&mut buffer);
+                &buffer
+            }
+            [HEAD_READ_VERSION, HEAD_READ_VERSION, HEAD_READ_VERSION] => {
+                buffer[..VERSION_SIZE].copy_from_slice(&self.get_version());
+                &buffer[..VERSION_SIZE]
+            }
+            [HEAD_RESET, HEAD_RESET, HEAD_RESET] => {
+                // module khởi động lại, không trả lời
+                self.reset();
+                &[]
+            }
+            // lệnh 3 byte: C1 C1 C1, C3 C3 C3, C4 C4 C4
+            [HEAD_READ_PARAMS | HEAD_READ_VERSION | HEAD_RESET, ..] if command.len() != 3 => {
+                return Err(E32Error::BadLength { expected: 3, actual: command.len() });
+            }
+            [head, ..] => return Err(E32Error::BadHeader(*head)),
+            [] => return Err(E32Error::BadLength { expected: 3, actual: 0 }),
         };
+
+        Ok(E32Response::from_bytes(response))
     }
 
-    pub fn get_params(&self, buffer: &mut [u8]) {
-        for i in 0..CONF_SIZE {
-            buffer[i] = self.params_list[i];
+    /// C0 ghi và lưu vào flash, C2 chỉ ghi tạm (mất khi reset)
+    pub fn write_params(&mut self, config: E32Config) {
+        self.config = E32Config { command: ConfigCommand::WriteSaved, ..config };
+        if config.command == ConfigCommand::WriteSaved {
+            self.saved_config = self.config;
+            if let Some(store) = self.store.as_mut() {
+                if let Err(e) = store.save(&<[u8; CONF_SIZE]>::from(self.config)) {
+                    log::warn!("E32: failed to save params: {e}");
+                }
+            }
+        }
+    }
+
+    /// Khởi động lại: nạp lại tham số đã lưu, bỏ các thay đổi từ C2
+    pub fn reset(&mut self) {
+        self.config = self.saved_config;
+        if let Some(registers) = self.registers.as_mut() {
+            registers.reset();
+            self.sync_registers();
+        }
+    }
+
+    /// E22/E220: cập nhật các trường dùng chung từ thanh ghi
+    fn sync_registers(&mut self) {
+        let Some(registers) = &self.registers else { return };
+        self.config.address = registers.address();
+        self.config.channel = registers.channel();
+        self.config.uart_bps = registers.uart_bps();
+        self.config.uart_parity = As32UartParity::from_bits(registers.parity_bits());
+        self.config.air_data_rate = registers.air_data_rate();
+        self.config.transmission_power = registers.transmission_power();
+        self.config.fixed_transmission = registers.fixed_transmission();
+    }
+
+    /// Trả lời C3 C3 C3: byte thứ hai là mã tần số (32 là 433 MHz, 45 là 868 MHz...).
+    /// Chỉ E32 dùng, lệnh của E22/E220 đi qua bản đồ thanh ghi.
+    pub fn get_version(&self) -> [u8; VERSION_SIZE] {
+        [HEAD_READ_VERSION, self.band.model_byte(), FIRMWARE_VERSION, MODULE_FEATURES]
+    }
+
+    /// Ghi nhận RSSI của gói vừa nhận
+    pub fn record_signal_rssi(&mut self, dbm: f64) {
+        self.rssi_signal_dbm = Some(dbm);
+    }
+
+    /// Ghi nhận mức năng lượng trên kênh khi không có gói
+    pub fn record_environment_rssi(&mut self, dbm: f64) {
+        self.rssi_environment_dbm = dbm;
+    }
+
+    /// Trả lời lệnh đọc RSSI `C0 C1 C2 C3 <reg> <len>` bằng `C1 <reg> <len> <giá trị...>`.
+    /// Mỗi giá trị là 256 + dBm (giống E22), 0 nếu chưa nhận gói nào.
+    /// Chỉ có trên E22/E220, ở chế độ Normal và WakeUp. Trả về `None` nếu không
+    /// phải lệnh RSSI để dữ liệu được phát bình thường (E32 phát nguyên 6 byte).
+    pub fn rssi_query(&self, command: &[u8]) -> Option<E32Response> {
+        if self.variant() == ModuleVariant::E32 || !matches!(self.state, E32State::Normal | E32State::WakeUp) {
+            return None;
+        }
+        if command.len() != RSSI_COMMAND_SIZE || command[..4] != RSSI_COMMAND_PREFIX {
+            return None;
         }
+        let (start, len) = (command[4], command[5]);
+        if len == 0 || start as usize + len as usize > 2 {
+            return Some(E32Response::none());
+        }
+        let mut response = [HEAD_READ_PARAMS, start, len, 0, 0];
+        for i in 0..len {
+            let dbm = match start + i {
+                x if x == RssiRegister::Environment as u8 => Some(self.rssi_environment_dbm),
+                x if x == RssiRegister::Signal as u8 => self.rssi_signal_dbm,
+                _ => None,
+            };
+            response[3 + i as usize] = dbm.map_or(0, |dbm| (256.0 + dbm).clamp(0.0, 255.0) as u8);
+        }
+        Some(E32Response::from_bytes(&response[..3 + len as usize]))
     }
-}
\ No newline at end of file
+
+    /// Thời gian đánh thức: wake-up time của E32 hoặc chu kỳ WOR của E22/E220
+    pub fn wake_up_ms(&self) -> u64 {
+        match &self.registers {
+            Some(registers) => registers.wor_cycle_ms(),
+            None => self.config.wake_up_time.as_millis(),
+        }
+    }
+
+    /// E22/E220 có bật RSSI byte: byte RSSI nối sau mỗi gói nhận được
+    pub fn rssi_byte(&self) -> Option<u8> {
+        let enabled = self.registers.as_ref().is_some_and(|r| r.rssi_byte_enabled());
+        let dbm = self.rssi_signal_dbm?;
+        enabled.then(|| (256.0 + dbm).clamp(0.0, 255.0) as u8)
+    }
+
+    /// NETID của E22, 0 với các dòng không có thanh ghi này.
+    /// Chỉ node ảo trên host so NETID và khóa, bridge coi bên phía PC luôn cùng mạng.
+    #[allow(dead_code)]
+    pub fn net_id(&self) -> u8 {
+        self.registers.as_ref().and_then(|r| r.net_id()).unwrap_or(0)
+    }
+
+    /// Khóa mã hóa của E22/E220, 0 với E32
+    #[allow(dead_code)]
+    pub fn crypt_key(&self) -> u16 {
+        self.registers.as_ref().map_or(0, |r| r.crypt_key())
+    }
+
+    /// E22/E220 bật LBT: chờ kênh trống rồi mới phát
+    pub fn lbt_enabled(&self) -> bool {
+        self.registers.as_ref().is_some_and(|r| r.lbt_enabled())
+    }
+
+    /// Địa chỉ module ADDH:ADDL
+    pub fn address(&self) -> u16 {
+        self.config.address
+    }
+
+    pub fn channel(&self) -> u8 {
+        self.config.channel
+    }
+
+    /// Nạp cấu hình 6 byte (không lưu vào flash), dùng trong test trên host
+    #[allow(dead_code)]
+    pub fn set_params(&mut self, params: &[u8], size: usize) -> Result<(), E32Error> {
+        let params = <[u8; CONF_SIZE]>::try_from(&params[..size.min(params.len())])
+            .map_err(|_| E32Error::BadLength { expected: CONF_SIZE, actual: size })?;
+        let config = E32Config::try_from(params)?;
+        self.config = E32Config { command: ConfigCommand::WriteSaved, ..config };
+        Ok(())
+    }
+
+    /// Trả về cấu hình hiện tại ở dạng 6 byte, header luôn là C0
+    pub fn get_params(&self, buffer: &mut [u8]) {
+        let params: [u8; CONF_SIZE] = self.config.into();
+        buffer[..CONF_SIZE].copy_from_slice(&params);
+    }
+}
+
+impl Default for E32Module {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,35 @@
+//! Lỗi của module E32 giả lập, để firmware và test phân biệt được nguyên nhân
+
+use core::fmt;
+
+use super::e32_module::E32State;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum E32Error {
+    /// Byte đầu của lệnh không phải lệnh nào module hiểu
+    BadHeader(u8),
+    /// Lệnh đúng header nhưng sai số byte
+    BadLength { expected: usize, actual: usize },
+    /// Trường mang giá trị để dành hoặc nằm ngoài dải cho phép
+    ReservedValue { field: &'static str, value: u8 },
+    /// Lệnh không được chấp nhận ở chế độ hiện tại
+    WrongMode(E32State),
+    /// Lỗi đọc/ghi UART (parity, overrun, lỗi driver...)
+    UartFault(String),
+}
+
+impl fmt::Display for E32Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            E32Error::BadHeader(head) => write!(f, "bad command header {:02X}", head),
+            E32Error::BadLength { expected, actual } => {
+                write!(f, "bad command length {} (expected {})", actual, expected)
+            }
+            E32Error::ReservedValue { field, value } => write!(f, "reserved {} value {:02X}", field, value),
+            E32Error::WrongMode(state) => write!(f, "command not accepted in {:?} mode", state),
+            E32Error::UartFault(reason) => write!(f, "UART fault: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for E32Error {}
@@ -0,0 +1,148 @@
+//! Cài đặt các trait trong `hal` cho driver của esp-idf-hal
+
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use esp_idf_hal::delay::{BLOCK, NON_BLOCK};
+use esp_idf_hal::gpio::{AnyIOPin, Input, Output, Pin, PinDriver};
+use esp_idf_hal::uart::{config, UartRxDriver, UartTxDriver};
+use esp_idf_sys::{esp, uart_set_baudrate, uart_set_parity, uart_set_rx_timeout, EspError};
+
+use super::hal::*;
+use super::spsc::Producer;
+
+/// Số byte tối đa mỗi lần task đọc UART lấy ra khỏi driver
+const READER_CHUNK: usize = 64;
+
+fn driver_parity(parity: Parity) -> config::Parity {
+    match parity {
+        Parity::None => config::Parity::ParityNone,
+        Parity::Odd => config::Parity::ParityOdd,
+        Parity::Even => config::Parity::ParityEven,
+    }
+}
+
+/// Nửa TX của một UART đã tách (`UartDriver::into_split`), nửa RX do
+/// `spawn_reader` giữ. Dùng cùng `QueuedSerial`.
+pub struct EspSerialTx<'d> {
+    tx: UartTxDriver<'d>,
+}
+
+impl<'d> EspSerialTx<'d> {
+    pub fn new(tx: UartTxDriver<'d>) -> Self {
+        Self { tx }
+    }
+}
+
+impl SerialPort for EspSerialTx<'_> {
+    /// Byte nhận được đi qua task đọc, không đọc ở đây
+    fn read(&mut self, _buf: &mut [u8]) -> anyhow::Result<usize> {
+        Ok(0)
+    }
+
+    fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        self.tx.write(data)?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        self.tx.wait_done(BLOCK)?;
+        Ok(())
+    }
+
+    // cấu hình thuộc về cả cổng UART nên đặt thẳng qua esp-idf
+    fn set_baudrate(&mut self, baudrate: u32) -> anyhow::Result<()> {
+        esp!(unsafe { uart_set_baudrate(self.tx.port(), baudrate) })?;
+        Ok(())
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> anyhow::Result<()> {
+        esp!(unsafe { uart_set_parity(self.tx.port(), driver_parity(parity).into()) })?;
+        Ok(())
+    }
+}
+
+/// Chờ byte đầu tiên rồi lấy thêm những byte đã nằm sẵn trong driver.
+/// Không chờ đủ cả `buf`: gói ngắn phải tới bridge ngay, không đợi byte sau.
+fn read_available(rx: &UartRxDriver<'_>, buf: &mut [u8]) -> Result<usize, EspError> {
+    let first = rx.read(&mut buf[..1], BLOCK)?;
+    Ok(first + rx.read(&mut buf[first..], NON_BLOCK)?)
+}
+
+/// Task đọc UART: chờ byte từ driver rồi đẩy vào hàng đợi SPSC cho bridge,
+/// nhờ vậy bridge không bao giờ chặn trên một cổng mà bỏ lỡ cổng kia.
+/// `flow_control`: hàng đợi đầy thì chờ thay vì bỏ byte, byte dồn lại trong
+/// driver và RTS của UART báo bên gửi dừng.
+/// `clock` phải là đồng hồ của bridge: mỗi lần đọc được ghi thời điểm nhận.
+pub fn spawn_reader<const N: usize, C: Clock + Send + 'static>(
+    rx: UartRxDriver<'static>,
+    mut producer: Producer<'static, N>,
+    flow_control: bool,
+    clock: C,
+) -> anyhow::Result<JoinHandle<()>> {
+    // driver báo có dữ liệu sau 1 ký tự lặng thay vì 10, để thời điểm nhận
+    // sát với lúc byte tới và hai gói cách nhau vài ký tự không bị gộp
+    esp!(unsafe { uart_set_rx_timeout(rx.port(), 1) })?;
+    Ok(thread::Builder::new().name("uart-rx".into()).spawn(move || {
+        let mut buf = [0u8; READER_CHUNK];
+        loop {
+            match read_available(&rx, &mut buf) {
+                Ok(n) => {
+                    let at_us = clock.now_us();
+                    let mut pushed = producer.push_slice(&buf[..n], at_us);
+                    while flow_control && pushed < n {
+                        thread::sleep(Duration::from_millis(1));
+                        pushed += producer.push_slice(&buf[pushed..n], at_us);
+                    }
+                    // bridge cộng vào bộ đếm và ghi log định kỳ
+                    producer.record_dropped(n - pushed);
+                }
+                Err(e) => log::warn!("UART{} RX: {e}", rx.port()),
+            }
+        }
+    })?)
+}
+
+pub struct EspModePins<'d, P0: Pin, P1: Pin> {
+    m0: PinDriver<'d, P0, Input>,
+    m1: PinDriver<'d, P1, Input>,
+    busy: Option<PinDriver<'d, AnyIOPin, Input>>,
+}
+
+impl<'d, P0: Pin, P1: Pin> EspModePins<'d, P0, P1> {
+    pub fn new(m0: PinDriver<'d, P0, Input>, m1: PinDriver<'d, P1, Input>) -> Self {
+        Self { m0, m1, busy: None }
+    }
+
+    /// Chân STM32 kéo HIGH khi chưa nhận thêm dữ liệu được
+    pub fn with_busy(mut self, busy: PinDriver<'d, AnyIOPin, Input>) -> Self {
+        self.busy = Some(busy);
+        self
+    }
+}
+
+impl<P0: Pin, P1: Pin> ModePins for EspModePins<'_, P0, P1> {
+    fn m0_is_high(&self) -> bool {
+        self.m0.is_high()
+    }
+
+    fn m1_is_high(&self) -> bool {
+        self.m1.is_high()
+    }
+
+    fn mcu_busy(&self) -> bool {
+        self.busy.as_ref().is_some_and(|pin| pin.is_high())
+    }
+}
+
+impl<T: Pin> AuxPin for PinDriver<'_, T, Output> {
+    fn set_high(&mut self) -> anyhow::Result<()> {
+        PinDriver::set_high(self)?;
+        Ok(())
+    }
+
+    fn set_low(&mut self) -> anyhow::Result<()> {
+        PinDriver::set_low(self)?;
+        Ok(())
+    }
+}
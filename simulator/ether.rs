@@ -0,0 +1,288 @@
+//! "Ether": tiến trình trên host nối nhiều E32 giả lập qua TCP localhost.
+//! Gói chỉ tới các node cùng tần số, cùng air data rate, cùng NETID và khóa
+//! mã hóa (E22/E220), và khớp địa chỉ đích.
+//!
+//! Mỗi message trên TCP: `[kind][len_hi][len_lo][body...]`
+//! - `KIND_CONFIG`: body = ADDH ADDL CHAN + air data rate (u32) + tần số nghe (u32 kHz)
+//!   + NETID + khóa mã hóa (u16)
+//! - `KIND_FRAME`:  body = air time (u32 us) + công suất phát (i8 dBm) + vị trí x, y (f32 m)
+//!   + tần số phát (u32 kHz) + ADDH ADDL CHAN của đích + dữ liệu
+//!
+//! Số nhiều byte đều là big endian.
+//!
+//! Node gửi gói ngay khi bắt đầu phát; bên nhận tự chờ hết air time rồi mới
+//! giao dữ liệu, nhờ đó phát hiện được va chạm theo đồng hồ của chính nó.
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use super::link::AirTarget;
+
+pub const DEFAULT_ETHER_ADDR: &str = "127.0.0.1:4032";
+
+const KIND_CONFIG: u8 = 0x01;
+const KIND_FRAME: u8 = 0x02;
+const MESSAGE_HEADER_SIZE: usize = 3;
+const CONFIG_BODY_SIZE: usize = 14;
+
+/// Cấu hình vô tuyến của một node, gửi lên ether mỗi khi thay đổi
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeConfig {
+    pub address: u16,
+    pub channel: u8,
+    pub air_data_rate: u32, // bps
+    pub frequency_khz: u32, // 0: kênh ngoài dải, không nghe được gì
+    pub net_id: u8,     // 0 với E32/E220
+    pub crypt_key: u16, // 0 với E32
+}
+
+impl NodeConfig {
+    fn encode(&self) -> [u8; CONFIG_BODY_SIZE] {
+        let [addh, addl] = self.address.to_be_bytes();
+        let [r0, r1, r2, r3] = self.air_data_rate.to_be_bytes();
+        let [f0, f1, f2, f3] = self.frequency_khz.to_be_bytes();
+        let [k0, k1] = self.crypt_key.to_be_bytes();
+        [addh, addl, self.channel, r0, r1, r2, r3, f0, f1, f2, f3, self.net_id, k0, k1]
+    }
+
+    fn decode(body: &[u8]) -> Option<Self> {
+        if body.len() != CONFIG_BODY_SIZE {
+            return None;
+        }
+        Some(Self {
+            address: u16::from_be_bytes([body[0], body[1]]),
+            channel: body[2],
+            air_data_rate: u32::from_be_bytes([body[3], body[4], body[5], body[6]]),
+            frequency_khz: u32::from_be_bytes([body[7], body[8], body[9], body[10]]),
+            net_id: body[11],
+            crypt_key: u16::from_be_bytes([body[12], body[13]]),
+        })
+    }
+
+    /// Node này có nghe được gói `frame` do `sender` phát không
+    pub fn hears(&self, sender: &NodeConfig, frame: &AirFrame) -> bool {
+        self.frequency_khz != 0
+            && self.frequency_khz == frame.frequency_khz
+            && self.air_data_rate == sender.air_data_rate
+            // khác NETID hoặc khác khóa thì module không nhận ra gói
+            && self.net_id == sender.net_id
+            && self.crypt_key == sender.crypt_key
+            && frame.target.accepts(self.address, self.channel)
+    }
+}
+
+fn write_message(stream: &mut impl Write, kind: u8, body: &[u8]) -> io::Result<()> {
+    let len = u16::try_from(body.len()).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "message too long"))?;
+    let [len_hi, len_lo] = len.to_be_bytes();
+    let mut message = Vec::with_capacity(MESSAGE_HEADER_SIZE + body.len());
+    message.extend_from_slice(&[kind, len_hi, len_lo]);
+    message.extend_from_slice(body);
+    stream.write_all(&message)
+}
+
+fn read_message(stream: &mut impl Read) -> io::Result<(u8, Vec<u8>)> {
+    let mut header = [0u8; MESSAGE_HEADER_SIZE];
+    stream.read_exact(&mut header)?;
+    let mut body = vec![0u8; u16::from_be_bytes([header[1], header[2]]) as usize];
+    stream.read_exact(&mut body)?;
+    Ok((header[0], body))
+}
+
+/// Một gói con trên không
+#[derive(Debug, Clone, PartialEq)]
+pub struct AirFrame {
+    pub target: AirTarget,
+    pub airtime_us: u32,
+    pub tx_power_dbm: i8,
+    pub x_m: f32,
+    pub y_m: f32,
+    pub frequency_khz: u32,
+    pub payload: Vec<u8>,
+}
+
+const FRAME_INFO_SIZE: usize = 17;
+
+impl AirFrame {
+    fn encode(&self) -> Vec<u8> {
+        let [addh, addl] = self.target.address.to_be_bytes();
+        let mut body = Vec::with_capacity(FRAME_INFO_SIZE + 3 + self.payload.len());
+        body.extend_from_slice(&self.airtime_us.to_be_bytes());
+        body.extend_from_slice(&self.tx_power_dbm.to_be_bytes());
+        body.extend_from_slice(&self.x_m.to_be_bytes());
+        body.extend_from_slice(&self.y_m.to_be_bytes());
+        body.extend_from_slice(&self.frequency_khz.to_be_bytes());
+        body.extend_from_slice(&[addh, addl, self.target.channel]);
+        body.extend_from_slice(&self.payload);
+        body
+    }
+
+    fn decode(body: &[u8]) -> Option<Self> {
+        let info = body.get(..FRAME_INFO_SIZE)?;
+        let (target, payload) = AirTarget::split_fixed(&body[FRAME_INFO_SIZE..])?;
+        Some(Self {
+            target,
+            airtime_us: u32::from_be_bytes(info[0..4].try_into().ok()?),
+            tx_power_dbm: info[4] as i8,
+            x_m: f32::from_be_bytes(info[5..9].try_into().ok()?),
+            y_m: f32::from_be_bytes(info[9..13].try_into().ok()?),
+            frequency_khz: u32::from_be_bytes(info[13..17].try_into().ok()?),
+            payload: payload.to_vec(),
+        })
+    }
+}
+
+struct EtherPeer {
+    config: Option<NodeConfig>,
+    stream: Arc<Mutex<TcpStream>>, // khoá riêng: gói từ nhiều node không ghi xen nhau
+}
+
+/// Tiến trình ether: nhận kết nối từ các node và chuyển gói giữa chúng
+pub struct Ether {
+    listener: TcpListener,
+    peers: Arc<Mutex<HashMap<usize, EtherPeer>>>,
+}
+
+impl Ether {
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            peers: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<std::net::SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Chạy mãi, mỗi node một thread
+    pub fn serve(&self) -> io::Result<()> {
+        for (id, stream) in self.listener.incoming().enumerate() {
+            let stream = stream?;
+            stream.set_nodelay(true)?;
+            let peer = EtherPeer { config: None, stream: Arc::new(Mutex::new(stream.try_clone()?)) };
+            self.peers.lock().unwrap().insert(id, peer);
+            let peers = self.peers.clone();
+            thread::spawn(move || {
+                let _ = Self::handle_peer(id, stream, &peers);
+                peers.lock().unwrap().remove(&id);
+            });
+        }
+        Ok(())
+    }
+
+    /// Các node đang nối, vẫn xem được sau khi `spawn`
+    pub fn peers(&self) -> EtherPeers {
+        EtherPeers(self.peers.clone())
+    }
+
+    /// Chạy `serve` trên một thread riêng
+    pub fn spawn(self) -> thread::JoinHandle<io::Result<()>> {
+        thread::spawn(move || self.serve())
+    }
+
+    fn handle_peer(id: usize, mut stream: TcpStream, peers: &Mutex<HashMap<usize, EtherPeer>>) -> io::Result<()> {
+        loop {
+            let (kind, body) = read_message(&mut stream)?;
+            match kind {
+                KIND_CONFIG => {
+                    if let Some(peer) = peers.lock().unwrap().get_mut(&id) {
+                        peer.config = NodeConfig::decode(&body);
+                    }
+                }
+                KIND_FRAME => {
+                    let Some(frame) = AirFrame::decode(&body) else { continue };
+                    // ghi sau khi nhả khoá, để một node đọc chậm không chặn cả ether
+                    for receiver in Self::receivers(id, &frame, peers) {
+                        // node nào lỗi kết nối thì thread của nó tự dọn
+                        let _ = write_message(&mut *receiver.lock().unwrap(), KIND_FRAME, &body);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Kết nối của các node nghe được gói `frame` do node `id` phát
+    fn receivers(id: usize, frame: &AirFrame, peers: &Mutex<HashMap<usize, EtherPeer>>) -> Vec<Arc<Mutex<TcpStream>>> {
+        let peers = peers.lock().unwrap();
+        let Some(sender) = peers.get(&id).and_then(|p| p.config) else { return Vec::new() };
+        peers
+            .iter()
+            .filter(|(other_id, peer)| **other_id != id && peer.config.is_some_and(|c| c.hears(&sender, frame)))
+            .map(|(_, peer)| peer.stream.clone())
+            .collect()
+    }
+}
+
+/// Danh sách node của một ether đang chạy
+#[derive(Clone)]
+pub struct EtherPeers(Arc<Mutex<HashMap<usize, EtherPeer>>>);
+
+impl EtherPeers {
+    /// Cấu hình ether đang dùng cho từng node, bỏ qua node chưa gửi cấu hình
+    pub fn configs(&self) -> Vec<NodeConfig> {
+        self.0.lock().unwrap().values().filter_map(|peer| peer.config).collect()
+    }
+}
+
+/// Đầu nối của một node tới ether
+pub struct EtherNode {
+    stream: TcpStream,
+    pending: Vec<u8>, // byte đã đọc nhưng chưa đủ một message
+}
+
+impl EtherNode {
+    pub fn connect(addr: impl ToSocketAddrs, config: NodeConfig) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        stream.set_nonblocking(true)?;
+        let mut node = Self { stream, pending: Vec::new() };
+        node.set_config(config)?;
+        Ok(node)
+    }
+
+    pub fn set_config(&mut self, config: NodeConfig) -> io::Result<()> {
+        self.stream.set_nonblocking(false)?;
+        let result = write_message(&mut self.stream, KIND_CONFIG, &config.encode());
+        self.stream.set_nonblocking(true)?;
+        result
+    }
+
+    pub fn send(&mut self, frame: &AirFrame) -> io::Result<()> {
+        self.stream.set_nonblocking(false)?;
+        let result = write_message(&mut self.stream, KIND_FRAME, &frame.encode());
+        self.stream.set_nonblocking(true)?;
+        result
+    }
+
+    /// Lấy một gói đã nhận nếu có, không chờ
+    pub fn try_recv(&mut self) -> io::Result<Option<AirFrame>> {
+        let mut chunk = [0u8; 512];
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => self.pending.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        while self.pending.len() >= MESSAGE_HEADER_SIZE {
+            let len = u16::from_be_bytes([self.pending[1], self.pending[2]]) as usize;
+            if self.pending.len() < MESSAGE_HEADER_SIZE + len {
+                break;
+            }
+            let (kind, body) = read_message(&mut self.pending.as_slice())?;
+            self.pending.drain(..MESSAGE_HEADER_SIZE + len);
+            if kind != KIND_FRAME {
+                continue;
+            }
+            if let Some(frame) = AirFrame::decode(&body) {
+                return Ok(Some(frame));
+            }
+        }
+        Ok(None)
+    }
+}
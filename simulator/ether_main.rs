@@ -0,0 +1,58 @@
+// Tiến trình ether chạy trên host (Linux), các VirtualNode kết nối vào đây
+//   cargo run --bin ether [ADDR]        chạy ether
+//   cargo run --bin ether node [ADDR]   chạy một node E32 ảo: mỗi dòng stdin là một gói,
+//                                       dữ liệu nhận được ghi ra stdout
+use std::io::{BufRead, Write};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use e32_simulator::e32_module::*;
+use e32_simulator::ether::*;
+use e32_simulator::hal::SystemClock;
+use e32_simulator::virtual_node::*;
+
+fn main() -> anyhow::Result<()> {
+    let mut args = std::env::args().skip(1);
+    match args.next() {
+        Some(command) if command == "node" => run_node(&args.next().unwrap_or_else(|| DEFAULT_ETHER_ADDR.to_string())),
+        addr => {
+            let ether = Ether::bind(addr.as_deref().unwrap_or(DEFAULT_ETHER_ADDR))?;
+            println!("E32 ether listening on {}", ether.local_addr()?);
+            ether.serve()?;
+            Ok(())
+        }
+    }
+}
+
+fn run_node(addr: &str) -> anyhow::Result<()> {
+    let mut node = VirtualNode::connect(addr, E32Module::new(), SystemClock::new())?;
+    eprintln!("E32 node at {:04X} channel {:02X} connected to {addr}", node.e32().address(), node.e32().channel());
+
+    // stdin chặn nên đọc ở thread riêng, vòng lặp chính chỉ lấy dòng đã có
+    let (lines_tx, lines) = mpsc::channel::<Vec<u8>>();
+    thread::spawn(move || {
+        for line in std::io::stdin().lock().split(b'\n') {
+            if line.map(|line| lines_tx.send(line)).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut stdout = std::io::stdout();
+    let mut buf = [0u8; 256];
+    loop {
+        match lines.try_recv() {
+            Ok(line) => node.write(&line),
+            Err(mpsc::TryRecvError::Empty) => {}
+            Err(mpsc::TryRecvError::Disconnected) => return Ok(()),
+        }
+        node.poll()?;
+        let n = node.read(&mut buf);
+        if n > 0 {
+            stdout.write_all(&buf[..n])?;
+            stdout.flush()?;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+}
@@ -0,0 +1,93 @@
+//! Tiêm lỗi cho đường truyền giả lập: mất gói, lỗi bit, lỗi chùm, va chạm.
+//! Dùng PRNG có seed nên mỗi lần chạy cho cùng một chuỗi lỗi.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FaultConfig {
+    /// Xác suất mất cả một gói con
+    pub loss_rate: f64,
+    /// Xác suất lật từng bit
+    pub bit_error_rate: f64,
+    /// Xác suất một gói con dính lỗi chùm
+    pub burst_rate: f64,
+    /// Số bit liên tiếp bị lật trong một lỗi chùm
+    pub burst_len: usize,
+    /// Hai gói chồng thời gian trên cùng kênh thì bên nhận mất cả hai
+    pub collisions: bool,
+    pub seed: u64,
+}
+
+impl FaultConfig {
+    /// Đường truyền hoàn hảo (mặc định)
+    pub const fn none() -> Self {
+        Self {
+            loss_rate: 0.0,
+            bit_error_rate: 0.0,
+            burst_rate: 0.0,
+            burst_len: 0,
+            collisions: false,
+            seed: 1,
+        }
+    }
+}
+
+/// xorshift64*: đủ tốt cho mô phỏng, không cần crate ngoài
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.max(1)) // xorshift không chạy với seed 0
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Số thực trong [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        probability > 0.0 && self.next_f64() < probability
+    }
+}
+
+pub struct FaultInjector {
+    config: FaultConfig,
+    rng: Rng,
+}
+
+impl FaultInjector {
+    pub fn new(config: FaultConfig) -> Self {
+        Self { config, rng: Rng::new(config.seed) }
+    }
+
+    pub fn config(&self) -> &FaultConfig {
+        &self.config
+    }
+
+    /// Áp lỗi lên một gói con. Trả về `false` nếu gói bị mất.
+    pub fn apply(&mut self, packet: &mut [u8]) -> bool {
+        if self.rng.chance(self.config.loss_rate) {
+            return false;
+        }
+        let bits = packet.len() * 8;
+        if self.config.bit_error_rate > 0.0 {
+            for bit in 0..bits {
+                if self.rng.chance(self.config.bit_error_rate) {
+                    packet[bit / 8] ^= 0x80 >> (bit % 8);
+                }
+            }
+        }
+        if bits > 0 && self.rng.chance(self.config.burst_rate) {
+            let start = (self.rng.next_u64() % bits as u64) as usize;
+            for bit in start..(start + self.config.burst_len).min(bits) {
+                packet[bit / 8] ^= 0x80 >> (bit % 8);
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,89 @@
+//! Tách khung theo khoảng lặng trên UART: gói kết thúc khi không có byte mới
+//! trong một số thời gian ký tự ở baud hiện tại. Thời gian lấy từ `Clock`
+//! nên chạy được với đồng hồ giả lập.
+
+use std::collections::VecDeque;
+
+use super::hal::Parity;
+
+/// Mặc định: lặng 3 ký tự thì đóng khung
+pub const DEFAULT_IDLE_CHARS: u64 = 3;
+
+/// Thời gian truyền một ký tự: start + 8 data + parity (nếu có) + stop
+pub fn char_time_us(baudrate: u32, parity: Parity) -> u64 {
+    let bits = if parity == Parity::None { 10 } else { 11 };
+    (bits * 1_000_000u64).div_ceil(baudrate.max(1) as u64)
+}
+
+pub struct IdleFramer {
+    char_time_us: u64,
+    idle_chars: u64,
+    frame_len: usize,  // số byte của khung đang mở
+    last_byte_us: u64, // thời điểm tới của byte cuối
+    closed: VecDeque<usize>, // khung đã đóng vì khoảng lặng giữa hai lần đọc, chưa lấy ra
+}
+
+impl IdleFramer {
+    pub fn new(baudrate: u32, parity: Parity) -> Self {
+        Self {
+            char_time_us: char_time_us(baudrate, parity),
+            idle_chars: DEFAULT_IDLE_CHARS,
+            frame_len: 0,
+            last_byte_us: 0,
+            closed: VecDeque::new(),
+        }
+    }
+
+    /// Gọi khi đổi baud/parity của UART
+    pub fn set_line(&mut self, baudrate: u32, parity: Parity) {
+        self.char_time_us = char_time_us(baudrate, parity);
+    }
+
+    /// Số thời gian ký tự lặng để đóng khung
+    pub fn set_idle_chars(&mut self, idle_chars: u64) {
+        self.idle_chars = idle_chars.max(1);
+    }
+
+    /// Khoảng lặng tính bằng us
+    pub fn idle_us(&self) -> u64 {
+        self.char_time_us * self.idle_chars
+    }
+
+    /// Thời điểm tới của byte thứ `index` trong `count` byte nhận cùng lúc
+    /// `at_us`: các byte đi liền nhau trên dây, byte cuối tới lúc `at_us`
+    pub fn arrival_us(&self, at_us: u64, index: usize, count: usize) -> u64 {
+        at_us.saturating_sub((count - 1 - index) as u64 * self.char_time_us)
+    }
+
+    /// Ghi nhận `count` byte tới liền nhau, byte cuối tới lúc `at_us`.
+    /// Khoảng lặng trước byte đầu đủ dài thì đóng khung đang mở, kể cả khi
+    /// cả hai khung được đọc ra trong cùng một vòng lặp.
+    pub fn on_bytes(&mut self, count: usize, at_us: u64) {
+        if count == 0 {
+            return;
+        }
+        let first_us = self.arrival_us(at_us, 0, count);
+        if self.frame_len > 0 && first_us.saturating_sub(self.last_byte_us) >= self.idle_us() {
+            self.closed.push_back(core::mem::take(&mut self.frame_len));
+        }
+        self.frame_len += count;
+        self.last_byte_us = at_us;
+    }
+
+    /// Trả về độ dài khung khi đã lặng đủ lâu, mỗi khung một lần, theo thứ tự tới
+    pub fn poll(&mut self, now_us: u64) -> Option<usize> {
+        if let Some(len) = self.closed.pop_front() {
+            return Some(len);
+        }
+        if self.frame_len == 0 || now_us.saturating_sub(self.last_byte_us) < self.idle_us() {
+            return None;
+        }
+        Some(core::mem::take(&mut self.frame_len))
+    }
+
+    /// Bỏ mọi khung chưa lấy ra (đổi chế độ)
+    pub fn clear(&mut self) {
+        self.frame_len = 0;
+        self.closed.clear();
+    }
+}
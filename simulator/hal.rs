@@ -0,0 +1,124 @@
+//! Các trait phần cứng mà bridge cần, để chạy được cả trên ESP32 lẫn trên host
+
+use super::spsc::Consumer;
+
+/// Bit chẵn lẻ của khung UART (luôn 8 bit dữ liệu, 1 stop bit)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    None,
+    Odd,
+    Even,
+}
+
+/// Cổng UART (PC hoặc STM32)
+pub trait SerialPort {
+    /// Đọc các byte đang có sẵn, trả về số byte đã đọc (0 nếu chưa có gì)
+    fn read(&mut self, buf: &mut [u8]) -> anyhow::Result<usize>;
+    /// Như `read` nhưng chỉ trả về các byte tới cùng một lúc, kèm thời điểm
+    /// nhận theo `Clock` của bridge. `None`: cổng không ghi lại thời điểm,
+    /// coi như byte vừa tới lúc đọc.
+    fn read_timed(&mut self, buf: &mut [u8]) -> anyhow::Result<(usize, Option<u64>)> {
+        Ok((self.read(buf)?, None))
+    }
+    /// Số byte nhận được nhưng bị bỏ trước khi tới `read` (hàng đợi của task
+    /// đọc đầy), tính từ lần gọi trước
+    fn take_rx_dropped(&mut self) -> u64 {
+        0
+    }
+    fn write(&mut self, data: &[u8]) -> anyhow::Result<()>;
+    /// Chờ phát hết byte đã ghi, gọi trước khi đổi baud/parity để không cắt cụt dữ liệu
+    fn flush(&mut self) -> anyhow::Result<()>;
+    fn set_baudrate(&mut self, baudrate: u32) -> anyhow::Result<()>;
+    fn set_parity(&mut self, parity: Parity) -> anyhow::Result<()>;
+}
+
+/// Cổng UART có task đọc riêng: byte nhận được nằm sẵn trong hàng đợi SPSC
+/// kèm thời điểm nhận, `read` không bao giờ chờ. Ghi và đổi cấu hình đi thẳng xuống `port`.
+pub struct QueuedSerial<'a, P, const N: usize> {
+    port: P,
+    rx: Consumer<'a, N>,
+}
+
+impl<'a, P: SerialPort, const N: usize> QueuedSerial<'a, P, N> {
+    pub fn new(port: P, rx: Consumer<'a, N>) -> Self {
+        Self { port, rx }
+    }
+}
+
+impl<P: SerialPort, const N: usize> SerialPort for QueuedSerial<'_, P, N> {
+    fn read(&mut self, buf: &mut [u8]) -> anyhow::Result<usize> {
+        Ok(self.rx.pop_into(buf))
+    }
+
+    fn read_timed(&mut self, buf: &mut [u8]) -> anyhow::Result<(usize, Option<u64>)> {
+        let (n, at_us) = self.rx.pop_timed(buf);
+        Ok((n, (n > 0).then_some(at_us)))
+    }
+
+    fn take_rx_dropped(&mut self) -> u64 {
+        self.rx.take_dropped() as u64
+    }
+
+    fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        self.port.write(data)
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        self.port.flush()
+    }
+
+    fn set_baudrate(&mut self, baudrate: u32) -> anyhow::Result<()> {
+        self.port.set_baudrate(baudrate)
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> anyhow::Result<()> {
+        self.port.set_parity(parity)
+    }
+}
+
+/// Các chân do STM32 điều khiển: M0, M1 chọn chế độ, chân báo bận tuỳ chọn
+pub trait ModePins {
+    fn m0_is_high(&self) -> bool;
+    fn m1_is_high(&self) -> bool;
+
+    /// STM32 đang bận, chưa nhận thêm dữ liệu. Mặc định: không nối chân bận.
+    fn mcu_busy(&self) -> bool {
+        false
+    }
+}
+
+/// Chân AUX báo trạng thái bận của module
+pub trait AuxPin {
+    fn set_high(&mut self) -> anyhow::Result<()>;
+    fn set_low(&mut self) -> anyhow::Result<()>;
+}
+
+/// Đồng hồ đơn điệu, đơn vị micro giây
+pub trait Clock {
+    fn now_us(&self) -> u64;
+}
+
+/// Đồng hồ dùng `std::time::Instant`, chạy được cả trên ESP32 (esp-idf) và host.
+/// Bản sao dùng chung mốc thời gian, task đọc UART và bridge phải dùng chung một đồng hồ.
+#[derive(Clone, Copy)]
+pub struct SystemClock {
+    start: std::time::Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self { start: std::time::Instant::now() }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now_us(&self) -> u64 {
+        self.start.elapsed().as_micros() as u64
+    }
+}
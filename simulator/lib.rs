@@ -0,0 +1,21 @@
+//! Lõi bridge E32 không phụ thuộc phần cứng, build được trên host để chạy test.
+//! Firmware (`main.rs`) khai báo lại cùng các file này dưới `mod simulator`.
+
+pub mod e32_module;
+pub mod band;
+pub mod error;
+pub mod buffer;
+pub mod spsc;
+pub mod param_store;
+pub mod register_map;
+pub mod command_parser;
+pub mod framer;
+pub mod hal;
+pub mod aux;
+pub mod link;
+pub mod fault;
+pub mod link_budget;
+pub mod bridge;
+pub mod mock;
+pub mod ether;
+pub mod virtual_node;
@@ -0,0 +1,99 @@
+//! Mô hình thời gian phát trên không (air time) của đường truyền E32
+
+use std::collections::VecDeque;
+
+use super::e32_module::{AirDataRate, ForwardErrorCorrection};
+
+/// E32 cắt dữ liệu thành các gói con 58 byte
+pub const SUB_PACKET_SIZE: usize = 58;
+/// Preamble mặc định tính theo byte ở tốc độ air data rate
+const PREAMBLE_BYTES: u64 = 4;
+
+/// Gửi tới địa chỉ này thì mọi node cùng kênh đều nhận
+pub const BROADCAST_ADDRESS: u16 = 0xFFFF;
+/// Chế độ fixed: 3 byte đầu của mỗi gói là ADDH ADDL CHAN của đích
+pub const FIXED_HEADER_SIZE: usize = 3;
+
+/// Đích của một gói trên không
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AirTarget {
+    pub address: u16,
+    pub channel: u8,
+}
+
+impl AirTarget {
+    /// Tách header ADDH ADDL CHAN, trả về đích và phần dữ liệu còn lại
+    pub fn split_fixed(frame: &[u8]) -> Option<(AirTarget, &[u8])> {
+        if frame.len() <= FIXED_HEADER_SIZE {
+            return None;
+        }
+        let target = AirTarget {
+            address: u16::from_be_bytes([frame[0], frame[1]]),
+            channel: frame[2],
+        };
+        Some((target, &frame[FIXED_HEADER_SIZE..]))
+    }
+
+    /// Node (address, channel) có nhận gói gửi tới đích này không.
+    /// Node có địa chỉ 0xFFFF nghe mọi gói cùng kênh.
+    pub fn accepts(&self, address: u16, channel: u8) -> bool {
+        self.channel == channel
+            && (self.address == BROADCAST_ADDRESS || address == BROADCAST_ADDRESS || self.address == address)
+    }
+}
+
+/// Thời gian phát một gói con `len` byte, tính bằng micro giây.
+/// FEC bật thì mỗi 4 bit dữ liệu tốn 5 bit trên không.
+pub fn airtime_us(air_data_rate: AirDataRate, fec: ForwardErrorCorrection, len: usize, extra_preamble_us: u64) -> u64 {
+    let bps = air_data_rate.as_bps() as u64;
+    let mut payload_bits = len as u64 * 8;
+    if fec == ForwardErrorCorrection::On {
+        payload_bits = payload_bits * 5 / 4;
+    }
+    let preamble_us = PREAMBLE_BYTES * 8 * 1_000_000 / bps;
+    preamble_us + extra_preamble_us + payload_bits * 1_000_000 / bps
+}
+
+/// Một gói con đang bay, tới nơi ở thời điểm `done_us`
+pub struct InFlight {
+    pub done_us: u64,
+    pub data: Vec<u8>,
+}
+
+/// Kênh bán song công: các gói con phát nối tiếp nhau, không chồng lên nhau
+pub struct LinkModel {
+    busy_until_us: u64,
+}
+
+impl LinkModel {
+    pub fn new() -> Self {
+        Self { busy_until_us: 0 }
+    }
+
+    /// Xếp lịch phát `data` và đưa từng gói con vào `queue`.
+    /// `extra_preamble_us` chỉ áp dụng cho gói con đầu tiên (preamble đánh thức của WakeUp).
+    pub fn send(
+        &mut self,
+        now_us: u64,
+        data: &[u8],
+        air_data_rate: AirDataRate,
+        fec: ForwardErrorCorrection,
+        extra_preamble_us: u64,
+        queue: &mut VecDeque<InFlight>,
+    ) {
+        let mut start = self.busy_until_us.max(now_us);
+        let mut extra = extra_preamble_us;
+        for chunk in data.chunks(SUB_PACKET_SIZE) {
+            start += airtime_us(air_data_rate, fec, chunk.len(), extra);
+            extra = 0;
+            queue.push_back(InFlight { done_us: start, data: chunk.to_vec() });
+        }
+        self.busy_until_us = start;
+    }
+}
+
+impl Default for LinkModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,42 @@
+//! Quỹ đường truyền: suy hao theo khoảng cách và RSSI tại bên nhận
+
+/// Nhiễu nền khi kênh trống
+pub const NOISE_FLOOR_DBM: f64 = -120.0;
+/// Hệ số suy hao mặc định (2.0 là không gian tự do, 2.7-3.5 là đô thị)
+pub const DEFAULT_PATH_LOSS_EXPONENT: f64 = 2.7;
+
+/// Suy hao không gian tự do ở 1 m: 20 log10(f MHz) - 27.55, khoảng 25.2 dB ở 433 MHz
+pub fn reference_loss_db(frequency_khz: u32) -> f64 {
+    20.0 * (frequency_khz as f64 / 1000.0).log10() - 27.55
+}
+
+/// Vị trí của một node và hệ số suy hao môi trường quanh nó
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub x_m: f64,
+    pub y_m: f64,
+    pub path_loss_exponent: f64,
+}
+
+impl Placement {
+    pub const fn new(x_m: f64, y_m: f64) -> Self {
+        Self { x_m, y_m, path_loss_exponent: DEFAULT_PATH_LOSS_EXPONENT }
+    }
+
+    pub fn distance_to(&self, other: &Placement) -> f64 {
+        ((self.x_m - other.x_m).powi(2) + (self.y_m - other.y_m).powi(2)).sqrt()
+    }
+
+    /// RSSI tại node này của một gói phát với `tx_power_dbm` từ `from` trên tần
+    /// số `frequency_khz`. Mô hình log-distance, dùng hệ số suy hao của bên nhận.
+    pub fn rssi_from(&self, from: &Placement, tx_power_dbm: f64, frequency_khz: u32) -> f64 {
+        let distance = self.distance_to(from).max(1.0);
+        tx_power_dbm - (reference_loss_db(frequency_khz) + 10.0 * self.path_loss_exponent * distance.log10())
+    }
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Self::new(0.0, 0.0)
+    }
+}
@@ -1,47 +1,76 @@
 use esp_idf_hal::gpio::*;
 use esp_idf_hal::prelude::*;
-use esp_idf_hal::delay::*;
 use esp_idf_hal::*;
-use esp_idf_svc::timer::*;
-use std::time::Duration;
-use core::sync::atomic::{AtomicBool, Ordering};
+use esp_idf_svc::nvs::EspDefaultNvsPartition;
 mod simulator{
     pub mod e32_module;
+    pub mod band;
+    pub mod error;
     pub mod buffer;
+    pub mod spsc;
+    pub mod param_store;
+    pub mod register_map;
+    pub mod command_parser;
+    pub mod framer;
+    pub mod hal;
+    pub mod aux;
+    pub mod link;
+    pub mod fault;
+    pub mod link_budget;
+    pub mod bridge;
+    pub mod esp_hal;
 }
 use simulator::e32_module::*;
+use simulator::band::*;
+use simulator::fault::*;
+use simulator::link_budget::*;
+use simulator::param_store::*;
+use simulator::register_map::*;
+use simulator::hal::*;
+use simulator::bridge::*;
 use simulator::buffer::*;
+use simulator::esp_hal::*;
+use simulator::spsc::*;
 
-const BUFF_SIZE: usize = 256;
-const MAX_WAIT_TIMES: u64 = 3;
-const BYTE_TIME_57600: u64 = 70*2;  // us
-const BYTE_TIME_19200: u64 = 416;   // us
-static TIMER0_EXPIRED: AtomicBool = AtomicBool::new(false);
-static TIMER1_EXPIRED: AtomicBool = AtomicBool::new(false);
+// hàng đợi byte nhận, task đọc UART ghi vào, bridge lấy ra
+static PC_RX: SpscQueue<BUFF_SIZE> = SpscQueue::new();
+static MCU_RX: SpscQueue<BUFF_SIZE> = SpscQueue::new();
+const MODULE_VARIANT: ModuleVariant = ModuleVariant::E32; // E32, E22 hoặc E220
+const MODULE_BAND: Band = Band::Band433; // E32-170, 433, 868 hoặc 915
+// RTS/CTS trên UART0 (GPIO22/GPIO19), chỉ bật khi PC có nối hai chân này
+const PC_FLOW_CONTROL: bool = false;
+// buffer đầy: Reject bỏ byte mới, OverwriteOldest bỏ byte cũ, Block giữ lại trong UART
+const OVERFLOW_POLICY: OverflowPolicy = OverflowPolicy::Reject;
+// chu kỳ ghi log số byte đã qua và bị bỏ của hai chiều
+const STATS_INTERVAL_US: u64 = 10_000_000;
+// số thời gian ký tự lặng để coi là hết một gói
+const FRAME_IDLE_CHARS: u64 = 3;
+// tiêm lỗi trên đường truyền giả lập khi thử nghiệm firmware STM32
+const LINK_FAULTS: FaultConfig = FaultConfig::none();
+// khoảng cách tới bên phát phía PC, dùng để tính RSSI và độ nhạy thu
+const PEER_DISTANCE_M: f64 = 1.0;
 
 
 fn main() -> anyhow::Result<()> {
     esp_idf_sys::link_patches();
+    // log (thống kê buffer, lỗi UART) ra console UART2 trong sdkconfig.defaults, không lẫn vào UART0
+    esp_idf_svc::log::EspLogger::initialize_default();
 
     // Init peripherals
     let peripherals = Peripherals::take().unwrap();
     let pins = peripherals.pins;
-    // init timer for UART
-    let timer_service = EspTimerService::new()?;
-    let timer0 = timer_service.timer(move || {
-        TIMER0_EXPIRED.store(true, Ordering::Relaxed);
-    })?;
-    let timer1 = timer_service.timer(move || {
-        TIMER1_EXPIRED.store(true, Ordering::Relaxed);
-    })?;
     // UART0: PC ↔ ESP32
     let uart0 = uart::UartDriver::new(
         peripherals.uart0,
         pins.gpio1,  // TX0
         pins.gpio3,  // RX0
-        Option::<AnyIOPin>::None,
-        Option::<AnyIOPin>::None,
-        &uart::config::Config::default().baudrate(Hertz(19200)),
+        PC_FLOW_CONTROL.then(|| pins.gpio19.downgrade()), // CTS0
+        PC_FLOW_CONTROL.then(|| pins.gpio22.downgrade()), // RTS0
+        &uart::config::Config::default().baudrate(Hertz(PC_BAUDRATE)).flow_control(if PC_FLOW_CONTROL {
+            uart::config::FlowControl::CTSRTS
+        } else {
+            uart::config::FlowControl::None
+        }),
     )?;
 
     // UART1: ESP32 ↔ STM32
@@ -51,123 +80,48 @@ fn main() -> anyhow::Result<()> {
         pins.gpio13, // RX1
         Option::<AnyIOPin>::None,   
         Option::<AnyIOPin>::None,
-        &uart::config::Config::default().baudrate(Hertz(57600)),
+        &uart::config::Config::default().baudrate(Hertz(MCU_BAUDRATE)),
     )?;
 
     let m0 = PinDriver::input(pins.gpio4)?; // M0
     let m1 = PinDriver::input(pins.gpio16)?; // M1
-    let mut aux = PinDriver::output(pins.gpio2)?; // AUX
-    aux.set_high()?; // AUX HIGH ban đầu
+    let aux = PinDriver::output(pins.gpio2)?; // AUX
+    let mut busy = PinDriver::input(pins.gpio17.downgrade())?; // STM32 báo bận
+    busy.set_pull(Pull::Down)?; // không nối thì coi như không bận
 
-    let mut lower_buffer = Buffer::new(BUFF_SIZE);
-    let mut upper_buffer = Buffer::new(BUFF_SIZE);
-    let mut e32 = E32Module::new();
+    let nvs = EspDefaultNvsPartition::take()?;
+    let mut e32 = E32Module::with_store(Box::new(NvsParamStore::new(nvs)?));
+    e32.set_variant(MODULE_VARIANT);
+    e32.set_band(MODULE_BAND);
 
-    let mut state;
-    let mut buf:[u8; BUFF_SIZE] = [0; BUFF_SIZE];
-    let mut buf1: [u8; BUFF_SIZE] = [0; BUFF_SIZE];
-    let mut params_buf = [0_u8; CONF_SIZE];
     uart0.write(b"ESP32 E32 Module Bridge\n")?;
-    let mut b = 0;
-    let mut b1 = 0;
-    loop {
-        // Check state change
-        let new_state = match (m0.is_high(), m1.is_high()) {
-            (false, false) => E32State::Normal,
-            (true, false) => E32State::WakeUp,
-            (false, true) => E32State::PowerSaving,
-            (true, true) => E32State::Sleep,
-        };
-        state = new_state;
-        match state {
-            E32State::Normal => {
-                // Read from UART0 (PC) → upper buffer
-
-                match uart0.read(&mut buf, 100){
-                    Ok (n)=> {b = n},
-                    Err(_) => {}
-                }
-                if b > 0 {
-                    for x in buf.iter_mut() {
-                        upper_buffer.enqueue(*x);
-                        *x = 0;
-                        b -= 1;
-                        if b == 0 {
-                            break;
-                        }
-                    }
-                    //let _ = buf.iter().map(|x| upper_buffer.enqueue(*x));
-                }
-                // Read from UART1 (STM32) → lower buffer
-                match uart1.read(&mut buf1, 100){
-                    Ok (n)=> b1 = n,
-                    Err(_) => {}
+    if let Some(khz) = e32.frequency_khz() {
+        uart0.write(format!("Carrier {}.{:03} MHz\n", khz / 1000, khz % 1000).as_bytes())?;
+    }
 
-                }
-                if b1 > 0 {
-                    for x in buf1.iter_mut() {
-                        lower_buffer.enqueue(*x);
-                        *x = 0;
-                        b1 -= 1;
-                        if b1 == 0 {
-                            break;
-                        }
-                    }
-                    //let _ = buf1.iter().map(|x| lower_buffer.enqueue(*x));
-                }
-                // Handle lower_buffer → UART0
-                if lower_buffer.available() > 0 {
-                    timer1.after(Duration::from_micros(BYTE_TIME_57600 * MAX_WAIT_TIMES))?;
-                    if TIMER1_EXPIRED.load(Ordering::Relaxed) {
-                        TIMER1_EXPIRED.store(false, Ordering::Relaxed);
-                        let n = lower_buffer.available();
-                        let data = lower_buffer.deallqueue();
-                        uart0.write(&data[..n])?;
-                    }
-                }
-                // Handle upper_buffer → UART1
-                if upper_buffer.available() > 0 {
-                    timer0.after(Duration::from_micros(BYTE_TIME_19200 * MAX_WAIT_TIMES))?;
-                    if TIMER0_EXPIRED.load(Ordering::Relaxed) {
-                        TIMER0_EXPIRED.store(false, Ordering::Relaxed);
-                        let n = upper_buffer.available();
-                        let data = upper_buffer.deallqueue();
-                        uart1.write(&data[..n])?;
-                    }
-                }
-            }
-            E32State::Sleep => {
-                if let Ok(_b) = uart1.read(&mut params_buf, BLOCK) {
-                    aux.set_low()?;
-                    let result = e32.input_command(&params_buf.as_ref(), params_buf.len());
-                    uart1.write(result.as_bytes())?;
-                    let new_baud_rate = match e32.uart_bps {
-                        UartBps::Bps1200 => Hertz(1200),
-                        UartBps::Bps2400 => Hertz(2400),
-                        UartBps::Bps4800 => Hertz(4800),
-                        UartBps::Bps9600 => Hertz(9600),
-                        UartBps::Bps19200 => Hertz(19200),
-                        UartBps::Bps38400 => Hertz(38400),
-                        UartBps::Bps57600 => Hertz(57600),
-                        UartBps::Bps115200 => Hertz(115200),
+    // mỗi UART một task đọc, bridge chỉ lấy byte từ hàng đợi nên không bao giờ chặn
+    let (uart0_tx, uart0_rx) = uart0.into_split();
+    let (uart1_tx, uart1_rx) = uart1.into_split();
+    let (pc_producer, pc_consumer) = PC_RX.split().ok_or_else(|| anyhow::anyhow!("PC_RX already split"))?;
+    let (mcu_producer, mcu_consumer) = MCU_RX.split().ok_or_else(|| anyhow::anyhow!("MCU_RX already split"))?;
+    // task đọc ghi thời điểm nhận theo cùng đồng hồ với bridge
+    let clock = SystemClock::new();
+    spawn_reader(uart0_rx, pc_producer, PC_FLOW_CONTROL, clock)?;
+    spawn_reader(uart1_rx, mcu_producer, false, clock)?;
 
-                    };
-                    let new_air_data_rate = match e32.air_data_rate {
-                        AirDataRate::Rate300 => Hertz(300),
-                        AirDataRate::Rate1200 => Hertz(1200),
-                        AirDataRate::Rate2400 => Hertz(2400),
-                        AirDataRate::Rate4800 => Hertz(4800),
-                        AirDataRate::Rate9600 => Hertz(9600),
-                        AirDataRate::Rate19200 => Hertz(19200),
-                    };
-                    uart0.change_baudrate(new_air_data_rate)?;
-                    uart1.change_baudrate(new_baud_rate)?;
-                    aux.set_high()?;
-                }
-            }
-            _ => {
-                // TODO: WAKE_UP / POWER_SAVING mode
-            }
-        }
-    }
+    let mut bridge = Bridge::new(
+        QueuedSerial::new(EspSerialTx::new(uart0_tx), pc_consumer),
+        QueuedSerial::new(EspSerialTx::new(uart1_tx), mcu_consumer),
+        EspModePins::new(m0, m1).with_busy(busy),
+        aux,
+        clock,
+        e32,
+    )?;
+    bridge.set_overflow_policy(OVERFLOW_POLICY);
+    bridge.set_flow_control(PC_FLOW_CONTROL);
+    bridge.set_stats_interval(Some(STATS_INTERVAL_US));
+    bridge.set_idle_chars(FRAME_IDLE_CHARS);
+    bridge.set_faults(LINK_FAULTS);
+    bridge.set_placement(Placement::default(), Placement::new(PEER_DISTANCE_M, 0.0));
+    bridge.run(|| delay::FreeRtos::delay_ms(1))
 }
@@ -0,0 +1,242 @@
+//! Bản giả lập của các trait trong `hal`, dùng để chạy bridge trong unit test trên host.
+//! Mỗi mock là một handle clone được: test giữ một bản, bridge giữ một bản.
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use super::hal::*;
+
+struct RxByte {
+    byte: u8,
+    framed: Parity,         // parity bên gửi đã dùng để đóng khung
+    arrival_us: Option<u64>, // `None`: coi như tới lúc bridge đọc
+}
+
+struct SerialState {
+    rx: VecDeque<RxByte>,
+    tx: Vec<u8>,
+    tx_pending: usize, // byte đã ghi nhưng chưa flush, còn trong TX FIFO
+    baudrate: u32,
+    parity: Parity,
+    rx_dropped: u64, // byte task đọc giả lập đã bỏ, chưa được bridge lấy
+}
+
+impl Default for SerialState {
+    fn default() -> Self {
+        Self { rx: VecDeque::new(), tx: Vec::new(), tx_pending: 0, baudrate: 0, parity: Parity::None, rx_dropped: 0 }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct MockSerial {
+    state: Rc<RefCell<SerialState>>,
+}
+
+impl MockSerial {
+    pub fn new(baudrate: u32) -> Self {
+        let serial = Self::default();
+        serial.state.borrow_mut().baudrate = baudrate;
+        serial
+    }
+
+    /// Đưa byte vào hàng đợi RX, đóng khung đúng parity hiện tại của cổng
+    pub fn inject(&self, data: &[u8]) {
+        let parity = self.parity();
+        self.inject_framed(data, parity);
+    }
+
+    /// Đưa byte vào hàng đợi RX như thể bên gửi dùng `parity`
+    pub fn inject_framed(&self, data: &[u8], parity: Parity) {
+        self.push_rx(data, parity, None);
+    }
+
+    /// Đưa byte vào hàng đợi RX như thể task đọc UART nhận được chúng lúc
+    /// `arrival_us`, trước khi bridge kịp đọc
+    pub fn inject_at(&self, data: &[u8], arrival_us: u64) {
+        let parity = self.parity();
+        self.push_rx(data, parity, Some(arrival_us));
+    }
+
+    /// Như task đọc bỏ `count` byte vì hàng đợi nhận đầy
+    pub fn drop_rx(&self, count: u64) {
+        self.state.borrow_mut().rx_dropped += count;
+    }
+
+    fn push_rx(&self, data: &[u8], framed: Parity, arrival_us: Option<u64>) {
+        let rx = data.iter().map(|&byte| RxByte { byte, framed, arrival_us });
+        self.state.borrow_mut().rx.extend(rx);
+    }
+
+    /// Lấy và xóa toàn bộ byte bridge đã ghi ra
+    pub fn take_written(&self) -> Vec<u8> {
+        std::mem::take(&mut self.state.borrow_mut().tx)
+    }
+
+    pub fn baudrate(&self) -> u32 {
+        self.state.borrow().baudrate
+    }
+
+    pub fn parity(&self) -> Parity {
+        self.state.borrow().parity
+    }
+
+    /// Số byte còn nằm trong hàng đợi RX, bridge chưa đọc
+    pub fn pending_rx(&self) -> usize {
+        self.state.borrow().rx.len()
+    }
+}
+
+impl SerialPort for MockSerial {
+    fn read(&mut self, buf: &mut [u8]) -> anyhow::Result<usize> {
+        Ok(self.read_timed(buf)?.0)
+    }
+
+    /// Trả về các byte cùng thời điểm nhận. Byte sai parity bị loại: trả về
+    /// các byte đúng phía trước, lần đọc kế tiếp báo lỗi parity và bỏ các byte sai.
+    fn read_timed(&mut self, buf: &mut [u8]) -> anyhow::Result<(usize, Option<u64>)> {
+        let mut state = self.state.borrow_mut();
+        let parity = state.parity;
+        let arrival_us = state.rx.front().and_then(|rx| rx.arrival_us);
+        let mut n = 0;
+        while n < buf.len() {
+            match state.rx.front() {
+                Some(rx) if rx.framed == parity && rx.arrival_us == arrival_us => {
+                    buf[n] = rx.byte;
+                    state.rx.pop_front();
+                    n += 1;
+                }
+                _ => break,
+            }
+        }
+        if n == 0 && !state.rx.is_empty() {
+            while state.rx.front().is_some_and(|rx| rx.framed != parity) {
+                state.rx.pop_front();
+            }
+            anyhow::bail!("parity error");
+        }
+        Ok((n, arrival_us))
+    }
+
+    fn take_rx_dropped(&mut self) -> u64 {
+        core::mem::take(&mut self.state.borrow_mut().rx_dropped)
+    }
+
+    fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        let mut state = self.state.borrow_mut();
+        state.tx.extend_from_slice(data);
+        state.tx_pending += data.len();
+        Ok(())
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        self.state.borrow_mut().tx_pending = 0;
+        Ok(())
+    }
+
+    /// Đổi cấu hình khi TX FIFO còn byte thì các byte đó hỏng, coi là lỗi
+    fn set_baudrate(&mut self, baudrate: u32) -> anyhow::Result<()> {
+        let mut state = self.state.borrow_mut();
+        anyhow::ensure!(state.tx_pending == 0, "baudrate changed with {} bytes in TX FIFO", state.tx_pending);
+        state.baudrate = baudrate;
+        Ok(())
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> anyhow::Result<()> {
+        let mut state = self.state.borrow_mut();
+        anyhow::ensure!(state.tx_pending == 0, "parity changed with {} bytes in TX FIFO", state.tx_pending);
+        state.parity = parity;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct MockModePins {
+    m0: Rc<Cell<bool>>,
+    m1: Rc<Cell<bool>>,
+    busy: Rc<Cell<bool>>,
+}
+
+impl MockModePins {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&self, m0: bool, m1: bool) {
+        self.m0.set(m0);
+        self.m1.set(m1);
+    }
+
+    pub fn set_busy(&self, busy: bool) {
+        self.busy.set(busy);
+    }
+}
+
+impl ModePins for MockModePins {
+    fn m0_is_high(&self) -> bool {
+        self.m0.get()
+    }
+
+    fn m1_is_high(&self) -> bool {
+        self.m1.get()
+    }
+
+    fn mcu_busy(&self) -> bool {
+        self.busy.get()
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct MockAux {
+    history: Rc<RefCell<Vec<bool>>>,
+}
+
+impl MockAux {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mức hiện tại của AUX, mặc định HIGH khi chưa được ghi
+    pub fn is_high(&self) -> bool {
+        self.history.borrow().last().copied().unwrap_or(true)
+    }
+
+    /// Mọi mức đã được ghi theo thứ tự
+    pub fn history(&self) -> Vec<bool> {
+        self.history.borrow().clone()
+    }
+}
+
+impl AuxPin for MockAux {
+    fn set_high(&mut self) -> anyhow::Result<()> {
+        self.history.borrow_mut().push(true);
+        Ok(())
+    }
+
+    fn set_low(&mut self) -> anyhow::Result<()> {
+        self.history.borrow_mut().push(false);
+        Ok(())
+    }
+}
+
+/// Đồng hồ chỉ chạy khi test gọi `advance`
+#[derive(Clone, Default)]
+pub struct MockClock {
+    now_us: Rc<Cell<u64>>,
+}
+
+impl MockClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, us: u64) {
+        self.now_us.set(self.now_us.get() + us);
+    }
+}
+
+impl Clock for MockClock {
+    fn now_us(&self) -> u64 {
+        self.now_us.get()
+    }
+}
@@ -0,0 +1,77 @@
+#[cfg(not(target_os = "espidf"))]
+use std::path::PathBuf;
+
+/// Nơi lưu tham số của lệnh C0, giữ lại qua các lần khởi động: 6 byte cấu hình
+/// của E32 hoặc ảnh thanh ghi của E22/E220. Bên đọc kiểm tra độ dài.
+pub trait ParamStore {
+    /// Trả về `None` nếu chưa lưu lần nào
+    fn load(&mut self) -> anyhow::Result<Option<Vec<u8>>>;
+    fn save(&mut self, params: &[u8]) -> anyhow::Result<()>;
+}
+
+/// Lưu vào một file nhị phân, dùng khi chạy trên máy host
+#[cfg(not(target_os = "espidf"))]
+pub struct FileParamStore {
+    path: PathBuf,
+}
+
+#[cfg(not(target_os = "espidf"))]
+impl FileParamStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[cfg(not(target_os = "espidf"))]
+impl ParamStore for FileParamStore {
+    fn load(&mut self) -> anyhow::Result<Option<Vec<u8>>> {
+        match std::fs::read(&self.path) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&mut self, params: &[u8]) -> anyhow::Result<()> {
+        std::fs::write(&self.path, params)?;
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "espidf")]
+pub use nvs::NvsParamStore;
+
+#[cfg(target_os = "espidf")]
+mod nvs {
+    use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs, NvsDefault};
+
+    use super::ParamStore;
+
+    const NAMESPACE: &str = "e32";
+    const KEY: &str = "params";
+    /// Ảnh tham số lớn nhất: 6 byte của E32, 9 thanh ghi của E22
+    const MAX_PARAMS_SIZE: usize = 16;
+
+    /// Lưu vào NVS trên ESP32
+    pub struct NvsParamStore {
+        nvs: EspNvs<NvsDefault>,
+    }
+
+    impl NvsParamStore {
+        pub fn new(partition: EspDefaultNvsPartition) -> anyhow::Result<Self> {
+            Ok(Self { nvs: EspNvs::new(partition, NAMESPACE, true)? })
+        }
+    }
+
+    impl ParamStore for NvsParamStore {
+        fn load(&mut self) -> anyhow::Result<Option<Vec<u8>>> {
+            let mut params = [0u8; MAX_PARAMS_SIZE];
+            Ok(self.nvs.get_raw(KEY, &mut params)?.map(|data| data.to_vec()))
+        }
+
+        fn save(&mut self, params: &[u8]) -> anyhow::Result<()> {
+            self.nvs.set_raw(KEY, params)?;
+            Ok(())
+        }
+    }
+}
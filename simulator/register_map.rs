@@ -0,0 +1,221 @@
+//! Giao thức cấu hình dạng thanh ghi của E22 và E220:
+//! `C0 addr len data` (ghi, lưu), `C2 addr len data` (ghi tạm), `C1 addr len` (đọc).
+//! Module trả lời `C1 addr len data`, lệnh sai định dạng thì trả `FF FF FF`.
+
+use super::e32_module::*;
+
+pub const REGISTER_HEADER_SIZE: usize = 3;
+pub const WRONG_FORMAT_RESPONSE: [u8; 3] = [0xFF, 0xFF, 0xFF];
+
+// firmware chỉ dựng dòng module chọn trong main.rs, các dòng khác dùng trên host
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleVariant {
+    E32,
+    E22,
+    E220,
+}
+
+/// Vị trí các thanh ghi của từng dòng module. `None` là không có thanh ghi đó.
+struct RegisterLayout {
+    size: usize,
+    addh: usize,
+    addl: usize,
+    net_id: Option<usize>,
+    reg0: usize, // UART baud, parity, air data rate
+    reg1: usize, // sub-packet, RSSI nhiễu nền, công suất phát
+    reg2: usize, // kênh
+    reg3: usize, // RSSI byte, fixed, relay, LBT, WOR
+    crypt_h: usize,
+    crypt_l: usize,
+    defaults: &'static [u8],
+}
+
+const E22_LAYOUT: RegisterLayout = RegisterLayout {
+    size: 9,
+    addh: 0x00,
+    addl: 0x01,
+    net_id: Some(0x02),
+    reg0: 0x03,
+    reg1: 0x04,
+    reg2: 0x05,
+    reg3: 0x06,
+    crypt_h: 0x07,
+    crypt_l: 0x08,
+    defaults: &[0x00, 0x00, 0x00, 0x62, 0x00, 0x17, 0x03, 0x00, 0x00],
+};
+
+const E220_LAYOUT: RegisterLayout = RegisterLayout {
+    size: 8,
+    addh: 0x00,
+    addl: 0x01,
+    net_id: None,
+    reg0: 0x02,
+    reg1: 0x03,
+    reg2: 0x04,
+    reg3: 0x05,
+    crypt_h: 0x06,
+    crypt_l: 0x07,
+    defaults: &[0x00, 0x00, 0x62, 0x00, 0x17, 0x03, 0x00, 0x00],
+};
+
+const MAX_REGISTERS: usize = 9;
+
+pub struct RegisterMap {
+    variant: ModuleVariant,
+    layout: &'static RegisterLayout,
+    registers: [u8; MAX_REGISTERS],
+    saved: [u8; MAX_REGISTERS], // giá trị đã lưu vào flash
+}
+
+impl RegisterMap {
+    /// `None` với E32 vì E32 dùng khung cấu hình 6 byte cũ
+    pub fn new(variant: ModuleVariant) -> Option<Self> {
+        let layout = match variant {
+            ModuleVariant::E32 => return None,
+            ModuleVariant::E22 => &E22_LAYOUT,
+            ModuleVariant::E220 => &E220_LAYOUT,
+        };
+        let mut registers = [0u8; MAX_REGISTERS];
+        registers[..layout.size].copy_from_slice(layout.defaults);
+        Some(Self { variant, layout, registers, saved: registers })
+    }
+
+    pub fn variant(&self) -> ModuleVariant {
+        self.variant
+    }
+
+    /// Xử lý một lệnh, trả về các byte module gửi lại
+    pub fn input_command(&mut self, command: &[u8]) -> E32Response {
+        let [head, addr, len, data @ ..] = command else {
+            return E32Response::from_bytes(&WRONG_FORMAT_RESPONSE);
+        };
+        let (addr, len) = (*addr as usize, *len as usize);
+        if len == 0 || addr + len > self.layout.size {
+            return E32Response::from_bytes(&WRONG_FORMAT_RESPONSE);
+        }
+        match *head {
+            HEAD_WRITE_SAVED | HEAD_WRITE_TEMPORARY if data.len() == len => {
+                self.registers[addr..addr + len].copy_from_slice(data);
+                if *head == HEAD_WRITE_SAVED {
+                    self.saved = self.registers;
+                }
+            }
+            HEAD_READ_PARAMS if data.is_empty() => {}
+            _ => return E32Response::from_bytes(&WRONG_FORMAT_RESPONSE),
+        }
+        let mut response = [0u8; REGISTER_HEADER_SIZE + MAX_REGISTERS];
+        response[..REGISTER_HEADER_SIZE].copy_from_slice(&[HEAD_READ_PARAMS, addr as u8, len as u8]);
+        response[REGISTER_HEADER_SIZE..REGISTER_HEADER_SIZE + len].copy_from_slice(&self.read(addr, len));
+        E32Response::from_bytes(&response[..REGISTER_HEADER_SIZE + len])
+    }
+
+    /// Khóa mã hóa chỉ ghi được, đọc luôn trả về 0
+    fn read(&self, addr: usize, len: usize) -> Vec<u8> {
+        (addr..addr + len)
+            .map(|i| if i == self.layout.crypt_h || i == self.layout.crypt_l { 0 } else { self.registers[i] })
+            .collect()
+    }
+
+    /// Bỏ các thay đổi tạm (C2), nạp lại giá trị đã lưu
+    pub fn reset(&mut self) {
+        self.registers = self.saved;
+    }
+
+    /// Ảnh các thanh ghi đã lưu bằng C0, để ghi ra ParamStore
+    pub fn saved(&self) -> &[u8] {
+        &self.saved[..self.layout.size]
+    }
+
+    /// Nạp ảnh đã lưu lúc khởi động, bỏ qua nếu độ dài không khớp dòng module
+    pub fn restore(&mut self, image: &[u8]) -> bool {
+        if image.len() != self.layout.size {
+            return false;
+        }
+        self.saved[..image.len()].copy_from_slice(image);
+        self.registers = self.saved;
+        true
+    }
+
+    pub fn address(&self) -> u16 {
+        u16::from_be_bytes([self.registers[self.layout.addh], self.registers[self.layout.addl]])
+    }
+
+    /// NETID: gói chỉ tới các node cùng NETID (E220 không có)
+    pub fn net_id(&self) -> Option<u8> {
+        self.layout.net_id.map(|i| self.registers[i])
+    }
+
+    pub fn channel(&self) -> u8 {
+        self.registers[self.layout.reg2]
+    }
+
+    pub fn uart_bps(&self) -> UartBps {
+        match self.registers[self.layout.reg0] >> 5 {
+            0b000 => UartBps::Bps1200,
+            0b001 => UartBps::Bps2400,
+            0b010 => UartBps::Bps4800,
+            0b011 => UartBps::Bps9600,
+            0b100 => UartBps::Bps19200,
+            0b101 => UartBps::Bps38400,
+            0b110 => UartBps::Bps57600,
+            _ => UartBps::Bps115200,
+        }
+    }
+
+    /// Bit 4-3 của REG0, cùng mã hóa với bit 7-6 của SPED trên E32
+    pub fn parity_bits(&self) -> u8 {
+        (self.registers[self.layout.reg0] >> 3) & 0b11
+    }
+
+    pub fn air_data_rate(&self) -> AirDataRate {
+        let code = self.registers[self.layout.reg0] & 0b111;
+        match (self.variant, code) {
+            (ModuleVariant::E22, 0b000) => AirDataRate::Rate300,
+            (ModuleVariant::E22, 0b001) => AirDataRate::Rate1200,
+            // E220: 000, 001, 010 đều là 2.4k
+            (_, 0b000..=0b010) => AirDataRate::Rate2400,
+            (_, 0b011) => AirDataRate::Rate4800,
+            (_, 0b100) => AirDataRate::Rate9600,
+            (_, 0b101) => AirDataRate::Rate19200,
+            (_, 0b110) => AirDataRate::Rate38400,
+            _ => AirDataRate::Rate62500,
+        }
+    }
+
+    pub fn transmission_power(&self) -> TransmissionPower {
+        match self.registers[self.layout.reg1] & 0b11 {
+            0b00 => TransmissionPower::Power20,
+            0b01 => TransmissionPower::Power17,
+            0b10 => TransmissionPower::Power14,
+            _ => TransmissionPower::Power10,
+        }
+    }
+
+    pub fn fixed_transmission(&self) -> FixedTransmission {
+        match (self.registers[self.layout.reg3] >> 6) & 0b1 {
+            0 => FixedTransmission::Transparent,
+            _ => FixedTransmission::PointToPoint,
+        }
+    }
+
+    /// REG3 bit 7: nối thêm 1 byte RSSI sau mỗi gói nhận được
+    pub fn rssi_byte_enabled(&self) -> bool {
+        self.registers[self.layout.reg3] & 0x80 != 0
+    }
+
+    /// REG3 bit 4: nghe trước khi phát (listen before talk)
+    pub fn lbt_enabled(&self) -> bool {
+        self.registers[self.layout.reg3] & 0x10 != 0
+    }
+
+    /// Chu kỳ WOR (wake on radio): (1 + mã) * 500 ms
+    pub fn wor_cycle_ms(&self) -> u64 {
+        ((self.registers[self.layout.reg3] & 0b111) as u64 + 1) * 500
+    }
+
+    /// Khóa mã hóa: hai bên phải cùng khóa mới giải mã được gói của nhau
+    pub fn crypt_key(&self) -> u16 {
+        u16::from_be_bytes([self.registers[self.layout.crypt_h], self.registers[self.layout.crypt_l]])
+    }
+}
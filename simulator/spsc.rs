@@ -0,0 +1,173 @@
+//! Hàng đợi byte một bên ghi, một bên đọc (SPSC), không khóa.
+//! Task đọc UART (hoặc callback ngắt) ghi vào, vòng lặp bridge lấy ra.
+//!
+//! Chép dữ liệu theo `ring_ranges` như `RingBuffer`, nhưng hai đầu là chỉ số
+//! atomic chạy trong [0, 2N) để phân biệt đầy với rỗng: bên ghi chỉ sửa
+//! `tail`, bên đọc chỉ sửa `head`.
+//!
+//! Mỗi byte kèm thời điểm bên ghi nhận được nó, để bridge tách khung và
+//! tính timeout theo lúc byte tới chứ không theo lúc vòng lặp lấy ra.
+
+use core::cell::UnsafeCell;
+use core::ops::Range;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+
+use super::buffer::ring_ranges;
+
+pub struct SpscQueue<const N: usize> {
+    data: UnsafeCell<[u8; N]>,
+    stamps: UnsafeCell<[u64; N]>, // thời điểm nhận của từng byte, us
+    head: AtomicUsize, // vị trí đọc, trong [0, 2N)
+    tail: AtomicUsize, // vị trí ghi, trong [0, 2N)
+    dropped: AtomicU32, // byte bên ghi bỏ vì đầy, chưa được bên đọc lấy (Xtensa không có atomic 64 bit)
+    split: AtomicBool,
+}
+
+// Chỉ truy cập qua một Producer và một Consumer, mỗi bên một ô nhớ riêng
+unsafe impl<const N: usize> Sync for SpscQueue<N> {}
+
+impl<const N: usize> SpscQueue<N> {
+    /// `const` để khai báo được dạng `static`
+    pub const fn new() -> Self {
+        Self {
+            data: UnsafeCell::new([0; N]),
+            stamps: UnsafeCell::new([0; N]),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            dropped: AtomicU32::new(0),
+            split: AtomicBool::new(false),
+        }
+    }
+
+    /// Lấy hai đầu của hàng đợi, chỉ được một lần
+    pub fn split(&self) -> Option<(Producer<'_, N>, Consumer<'_, N>)> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        Some((Producer { queue: self }, Consumer { queue: self }))
+    }
+
+    fn advance(index: usize, count: usize) -> usize {
+        (index + count) % (2 * N)
+    }
+
+    fn distance(head: usize, tail: usize) -> usize {
+        (tail + 2 * N - head) % (2 * N)
+    }
+
+    /// Các ô của `len` byte bắt đầu từ chỉ số `index`
+    fn ranges(index: usize, len: usize) -> (Range<usize>, Range<usize>) {
+        if len == 0 {
+            return (0..0, 0..0);
+        }
+        ring_ranges(index % N, len, N)
+    }
+
+    /// Con trỏ tới ô đầu của `range`, không tạo tham chiếu tới cả mảng
+    fn slots(&self, range: &Range<usize>) -> *mut u8 {
+        unsafe { (self.data.get() as *mut u8).add(range.start) }
+    }
+
+    /// Con trỏ tới thời điểm nhận của byte ở chỉ số `index`
+    fn stamp(&self, index: usize) -> *mut u64 {
+        unsafe { (self.stamps.get() as *mut u64).add(index % N) }
+    }
+}
+
+impl<const N: usize> Default for SpscQueue<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Đầu ghi, giao cho task đọc UART
+pub struct Producer<'a, const N: usize> {
+    queue: &'a SpscQueue<N>,
+}
+
+impl<const N: usize> Producer<'_, N> {
+    /// Số byte còn ghi được
+    pub fn free(&self) -> usize {
+        let head = self.queue.head.load(Ordering::Acquire);
+        let tail = self.queue.tail.load(Ordering::Relaxed);
+        N - SpscQueue::<N>::distance(head, tail)
+    }
+
+    /// Ghi nhiều byte nhận được lúc `at_us`, trả về số byte đã ghi (dừng khi đầy)
+    pub fn push_slice(&mut self, bytes: &[u8], at_us: u64) -> usize {
+        let tail = self.queue.tail.load(Ordering::Relaxed);
+        let count = bytes.len().min(self.free());
+        let (first, second) = SpscQueue::<N>::ranges(tail, count);
+        let (bytes_first, bytes_second) = bytes[..count].split_at(first.len());
+        // các ô này bên đọc chưa thấy cho tới khi `tail` được cập nhật
+        unsafe {
+            ptr::copy_nonoverlapping(bytes_first.as_ptr(), self.queue.slots(&first), first.len());
+            ptr::copy_nonoverlapping(bytes_second.as_ptr(), self.queue.slots(&second), second.len());
+            for i in 0..count {
+                self.queue.stamp(SpscQueue::<N>::advance(tail, i)).write(at_us);
+            }
+        }
+        self.queue.tail.store(SpscQueue::<N>::advance(tail, count), Ordering::Release);
+        count
+    }
+
+    /// Ghi nhận `count` byte không vào được hàng đợi
+    pub fn record_dropped(&mut self, count: usize) {
+        self.queue.dropped.fetch_add(count as u32, Ordering::Relaxed);
+    }
+}
+
+// Task đọc UART chạy trên thread riêng
+unsafe impl<const N: usize> Send for Producer<'_, N> {}
+
+/// Đầu đọc, giao cho bridge
+pub struct Consumer<'a, const N: usize> {
+    queue: &'a SpscQueue<N>,
+}
+
+impl<const N: usize> Consumer<'_, N> {
+    /// Số byte đang chờ đọc
+    pub fn available(&self) -> usize {
+        let tail = self.queue.tail.load(Ordering::Acquire);
+        let head = self.queue.head.load(Ordering::Relaxed);
+        SpscQueue::<N>::distance(head, tail)
+    }
+
+    /// Đọc tối đa `out.len()` byte, trả về số byte đã đọc
+    pub fn pop_into(&mut self, out: &mut [u8]) -> usize {
+        let head = self.queue.head.load(Ordering::Relaxed);
+        let count = out.len().min(self.available());
+        let (first, second) = SpscQueue::<N>::ranges(head, count);
+        let (out_first, out_second) = out[..count].split_at_mut(first.len());
+        // bên ghi không đụng tới các ô này cho tới khi `head` được cập nhật
+        unsafe {
+            ptr::copy_nonoverlapping(self.queue.slots(&first), out_first.as_mut_ptr(), first.len());
+            ptr::copy_nonoverlapping(self.queue.slots(&second), out_second.as_mut_ptr(), second.len());
+        }
+        self.queue.head.store(SpscQueue::<N>::advance(head, count), Ordering::Release);
+        count
+    }
+
+    /// Số byte bên ghi đã bỏ từ lần gọi trước
+    pub fn take_dropped(&mut self) -> u32 {
+        self.queue.dropped.swap(0, Ordering::Relaxed)
+    }
+
+    /// Như `pop_into` nhưng chỉ lấy các byte nhận cùng một lúc, trả về số byte
+    /// và thời điểm nhận của chúng
+    pub fn pop_timed(&mut self, out: &mut [u8]) -> (usize, u64) {
+        let head = self.queue.head.load(Ordering::Relaxed);
+        let count = out.len().min(self.available());
+        if count == 0 {
+            return (0, 0);
+        }
+        // ô trong [head, head + count) đã được bên ghi công bố qua `tail`
+        let stamp = |i| unsafe { self.queue.stamp(SpscQueue::<N>::advance(head, i)).read() };
+        let at_us = stamp(0);
+        let run = (1..count).find(|&i| stamp(i) != at_us).unwrap_or(count);
+        (self.pop_into(&mut out[..run]), at_us)
+    }
+}
+
+unsafe impl<const N: usize> Send for Consumer<'_, N> {}
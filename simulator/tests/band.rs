@@ -0,0 +1,58 @@
+//! Dải tần theo dòng module: số kênh, tần số, mã tần số và suy hao tham chiếu
+
+use e32_simulator::band::*;
+use e32_simulator::e32_module::*;
+use e32_simulator::link_budget::*;
+use e32_simulator::register_map::ModuleVariant;
+
+const BANDS: [Band; 4] = [Band::Band170, Band::Band433, Band::Band868, Band::Band915];
+
+#[test]
+fn channel_limits_depend_on_variant() {
+    for band in BANDS {
+        assert!(band.is_valid_channel(ModuleVariant::E32, 0x1B));
+        assert!(!band.is_valid_channel(ModuleVariant::E32, 0x20));
+    }
+    for variant in [ModuleVariant::E22, ModuleVariant::E220] {
+        assert_eq!(Band::Band433.max_channel(variant), 83);
+        assert_eq!(Band::Band433.frequency_khz(variant, 83), Some(493_125));
+        assert_eq!(Band::Band868.frequency_khz(variant, 0x17), Some(873_125));
+        assert_eq!(Band::Band915.frequency_khz(variant, 80), Some(930_125));
+        assert_eq!(Band::Band915.frequency_khz(variant, 81), None);
+    }
+    assert_eq!(Band::Band433.frequency_khz(ModuleVariant::E32, 0x17), Some(433_000));
+    assert_eq!(Band::Band170.frequency_khz(ModuleVariant::E32, 0x1B), Some(173_500));
+}
+
+#[test]
+fn version_reports_band() {
+    for (band, model) in BANDS.into_iter().zip([0x3A, 0x32, 0x45, 0x44]) {
+        let mut e32 = E32Module::new();
+        e32.set_band(band);
+        e32.set_state(E32State::Sleep);
+        assert_eq!(e32.input_command(&[0xC3; 3], 3).unwrap().as_bytes()[..2], [0xC3, model]);
+    }
+}
+
+#[test]
+fn version_command_only_on_e32() {
+    for variant in [ModuleVariant::E22, ModuleVariant::E220] {
+        for band in BANDS {
+            let mut e32 = E32Module::new();
+            e32.set_variant(variant);
+            e32.set_band(band);
+            e32.set_state(E32State::Sleep);
+            assert_eq!(e32.input_command(&[0xC3; 3], 3).unwrap().as_bytes(), [0xFF; 3], "{variant:?} {band:?}");
+        }
+    }
+}
+
+#[test]
+fn reference_loss_grows_with_frequency() {
+    assert!((reference_loss_db(433_000) - 25.2).abs() < 0.05);
+    assert!((reference_loss_db(868_000) - 31.2).abs() < 0.05);
+    let (near, far) = (Placement::default(), Placement::new(100.0, 0.0));
+    let loss_433 = 20.0 - far.rssi_from(&near, 20.0, 433_000);
+    let loss_915 = 20.0 - far.rssi_from(&near, 20.0, 915_000);
+    assert!((loss_915 - loss_433 - 20.0 * (915.0f64 / 433.0).log10()).abs() < 1e-9);
+}
@@ -0,0 +1,443 @@
+//! Chạy bridge trên host với các mock trong `mock.rs` và đồng hồ giả lập
+
+use e32_simulator::bridge::*;
+use e32_simulator::e32_module::*;
+use e32_simulator::error::E32Error;
+use e32_simulator::fault::*;
+use e32_simulator::hal::{Clock, Parity};
+use e32_simulator::link::*;
+use e32_simulator::mock::*;
+use e32_simulator::register_map::ModuleVariant;
+
+type MockBridge = Bridge<MockSerial, MockSerial, MockModePins, MockAux, MockClock>;
+
+struct Rig {
+    bridge: MockBridge,
+    pc: MockSerial,
+    mcu: MockSerial,
+    pins: MockModePins,
+    aux: MockAux,
+    clock: MockClock,
+}
+
+impl Rig {
+    /// Bridge đã qua tự kiểm tra sau khi bật nguồn, đang ở chế độ Normal
+    fn new() -> Self {
+        Self::with_module(E32Module::new())
+    }
+
+    fn with_module(e32: E32Module) -> Self {
+        let pc = MockSerial::new(PC_BAUDRATE);
+        let mcu = MockSerial::new(MCU_BAUDRATE);
+        let (pins, aux, clock) = (MockModePins::new(), MockAux::new(), MockClock::new());
+        let bridge = Bridge::new(pc.clone(), mcu.clone(), pins.clone(), aux.clone(), clock.clone(), e32).unwrap();
+        let mut rig = Self { bridge, pc, mcu, pins, aux, clock };
+        assert!(!rig.aux.is_high());
+        rig.run_for(40_000);
+        assert!(rig.aux.is_high());
+        rig
+    }
+
+    /// Một vòng lặp rồi tiến đồng hồ 100 us. Như `Bridge::run`, lỗi UART chỉ bị bỏ qua.
+    fn step(&mut self) {
+        if let Err(e) = self.bridge.poll() {
+            assert!(matches!(e.downcast_ref::<E32Error>(), Some(E32Error::UartFault(_))), "{e}");
+        }
+        self.clock.advance(100);
+    }
+
+    /// Chạy bridge trong `duration_us`
+    fn run_for(&mut self, duration_us: u64) {
+        for _ in 0..duration_us / 100 {
+            self.step();
+        }
+    }
+
+    /// Chạy tới khi `port` nhận đủ `want` byte (tối đa 10 s giả lập)
+    fn run_until(&mut self, port: &MockSerial, want: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        for _ in 0..100_000 {
+            if out.len() >= want {
+                break;
+            }
+            self.step();
+            out.extend(port.take_written());
+        }
+        out
+    }
+
+    /// Như `run_until`, kèm thời gian giả lập đã trôi tới khi nhận đủ
+    fn timed_until(&mut self, port: &MockSerial, want: usize) -> (Vec<u8>, u64) {
+        let start = self.clock.now_us();
+        let out = self.run_until(port, want);
+        (out, self.clock.now_us() - start)
+    }
+
+    /// Đổi M0/M1 rồi chờ chế độ mới có hiệu lực
+    fn set_mode(&mut self, m0: bool, m1: bool) {
+        self.pins.set(m0, m1);
+        self.run_for(10_000);
+    }
+
+    fn command(&mut self, command: &[u8], response_len: usize) -> Vec<u8> {
+        self.mcu.inject(command);
+        let response = self.run_until(&self.mcu.clone(), response_len);
+        self.run_for(5_000);
+        response
+    }
+}
+
+#[test]
+fn transparent_both_directions() {
+    let mut rig = Rig::new();
+    rig.pc.inject(b"hello");
+    assert_eq!(rig.run_until(&rig.mcu.clone(), 5), b"hello");
+
+    rig.mcu.inject(&[7u8; 120]);
+    assert_eq!(rig.run_until(&rig.pc.clone(), 120), [7u8; 120]);
+}
+
+#[test]
+fn aux_low_while_sending() {
+    let mut rig = Rig::new();
+    rig.mcu.inject(&[1u8; 40]);
+    rig.run_for(5_000);
+    assert!(!rig.aux.is_high());
+    assert_eq!(rig.run_until(&rig.pc.clone(), 40).len(), 40);
+    rig.run_for(1_000);
+    assert!(rig.aux.is_high());
+}
+
+#[test]
+fn delivery_waits_for_modeled_air_time() {
+    let mut rig = Rig::new();
+    // mặc định 2.4k, FEC bật: 10 byte mất 54999 us trên không
+    let air_us = airtime_us(AirDataRate::Rate2400, ForwardErrorCorrection::On, 10, 0);
+    let start = rig.clock.now_us();
+    rig.mcu.inject(&[5; 10]);
+    let mut received = Vec::new();
+    while received.is_empty() {
+        rig.step();
+        received = rig.pc.take_written();
+        if received.is_empty() {
+            assert!(!rig.aux.is_high(), "AUX high at {} us", rig.clock.now_us() - start);
+        }
+        assert!(rig.clock.now_us() - start < 1_000_000);
+    }
+    assert_eq!(received, [5; 10]);
+    // khung đóng sau 3 ký tự lặng ở 9600 bps, rồi phát đúng air time
+    let elapsed = rig.clock.now_us() - start;
+    assert!((air_us..air_us + 10_000).contains(&elapsed), "elapsed {elapsed} us, air time {air_us} us");
+}
+
+#[test]
+fn wake_up_mode_sends_long_preamble() {
+    let mut rig = Rig::new();
+    let air_us = airtime_us(AirDataRate::Rate2400, ForwardErrorCorrection::On, 10, 0);
+    rig.mcu.inject(&[6; 10]);
+    let (_, normal_us) = rig.timed_until(&rig.pc.clone(), 10);
+
+    // WakeUp: preamble kéo dài thêm wake-up time mặc định 250 ms
+    rig.set_mode(true, false);
+    rig.mcu.inject(&[6; 10]);
+    let (received, wake_up_us) = rig.timed_until(&rig.pc.clone(), 10);
+    assert_eq!(received, [6; 10]);
+    assert!(normal_us < air_us + 10_000);
+    assert!((normal_us + 249_000..normal_us + 251_000).contains(&wake_up_us), "{normal_us} us vs {wake_up_us} us");
+}
+
+#[test]
+fn power_saving_only_receives_at_wake_cycles() {
+    let mut rig = Rig::new();
+    rig.set_mode(false, true);
+    // không phát: dữ liệu từ STM32 bị bỏ
+    rig.mcu.inject(&[7; 10]);
+    rig.run_for(1_000_000);
+    assert!(rig.pc.take_written().is_empty());
+    assert_eq!(rig.mcu.pending_rx(), 0);
+
+    // nhận: mỗi gói chờ tới chu kỳ thức 250 ms tiếp theo
+    rig.pc.inject(&[1; 10]);
+    assert_eq!(rig.run_until(&rig.mcu.clone(), 10), [1; 10]);
+    rig.pc.inject(&[2; 10]);
+    let (received, gap_us) = rig.timed_until(&rig.mcu.clone(), 10);
+    assert_eq!(received, [2; 10]);
+    assert!((249_000..251_000).contains(&gap_us), "{gap_us} us");
+}
+
+#[test]
+fn sleep_mode_reads_params() {
+    let mut rig = Rig::new();
+    rig.set_mode(true, true);
+    assert_eq!(rig.command(&[0xC1, 0xC1, 0xC1], 6), [0xC0, 0x00, 0x00, 0x1A, 0x17, 0x44]);
+    assert_eq!(rig.command(&[0xC3, 0xC3, 0xC3], 4), [0xC3, 0x32, 0x27, 0x14]);
+}
+
+#[test]
+fn sleep_discards_air_data() {
+    let mut rig = Rig::new();
+    rig.set_mode(true, true);
+    rig.pc.inject(&[9u8; 300]);
+    rig.run_for(50_000);
+    assert_eq!(rig.pc.pending_rx(), 0);
+
+    // về Normal: không giao lại phần đã bỏ, dữ liệu mới vẫn qua bình thường
+    rig.set_mode(false, false);
+    rig.run_for(500_000);
+    assert!(rig.mcu.take_written().is_empty());
+    rig.pc.inject(b"after");
+    assert_eq!(rig.run_until(&rig.mcu.clone(), 5), b"after");
+}
+
+#[test]
+fn commands_ignored_outside_sleep() {
+    let mut rig = Rig::new();
+    // ở Normal, C1 C1 C1 chỉ là dữ liệu và được phát đi
+    rig.mcu.inject(&[0xC1, 0xC1, 0xC1]);
+    assert_eq!(rig.run_until(&rig.pc.clone(), 3), [0xC1, 0xC1, 0xC1]);
+    assert!(rig.mcu.take_written().is_empty());
+}
+
+#[test]
+fn fixed_transmission_filters_by_address_and_channel() {
+    let mut rig = Rig::new();
+    rig.set_mode(true, true);
+    assert_eq!(rig.command(&[0xC0, 0x00, 0x05, 0x1A, 0x17, 0xC4], 6).len(), 6);
+    rig.set_mode(false, false);
+
+    rig.pc.inject(&[0x00, 0x06, 0x17, 1, 2, 3]);
+    rig.run_for(200_000);
+    assert!(rig.mcu.take_written().is_empty());
+
+    rig.pc.inject(&[0x00, 0x05, 0x17, 1, 2, 3]);
+    assert_eq!(rig.run_until(&rig.mcu.clone(), 3), [1, 2, 3]);
+
+    // broadcast: mọi node cùng kênh đều nhận
+    rig.pc.inject(&[0xFF, 0xFF, 0x17, 4]);
+    assert_eq!(rig.run_until(&rig.mcu.clone(), 1), [4]);
+    rig.pc.inject(&[0xFF, 0xFF, 0x16, 4]);
+    rig.run_for(200_000);
+    assert!(rig.mcu.take_written().is_empty());
+}
+
+#[test]
+fn sped_parity_applies_to_mcu_uart() {
+    let mut rig = Rig::new();
+    rig.set_mode(true, true);
+    // SPED 0x5A: 8O1, 9600 bps, air 2.4k
+    assert_eq!(rig.command(&[0xC0, 0, 0, 0x5A, 0x17, 0x44], 6), [0xC0, 0, 0, 0x5A, 0x17, 0x44]);
+    assert_eq!(rig.mcu.parity(), Parity::Odd);
+    assert_eq!(rig.mcu.baudrate(), 9600);
+
+    rig.set_mode(false, false);
+    rig.mcu.inject_framed(b"bad", Parity::None);
+    rig.mcu.inject(b"ok");
+    assert_eq!(rig.run_until(&rig.pc.clone(), 2), b"ok");
+}
+
+#[test]
+fn baud_change_only_touches_mcu_uart() {
+    let mut rig = Rig::new();
+    rig.set_mode(true, true);
+    // 8E1, 115200 bps, air 300 bps
+    let command = [0xC2, 0, 0, 0b1011_1000, 0x17, 0x44];
+    assert_eq!(rig.command(&command, 6), command);
+    assert_eq!(rig.mcu.baudrate(), 115200);
+    assert_eq!(rig.mcu.parity(), Parity::Even);
+    assert_eq!(rig.pc.baudrate(), PC_BAUDRATE);
+}
+
+#[test]
+fn busy_mcu_bounds_pending_data_without_flow_control() {
+    let mut rig = Rig::new();
+    rig.pins.set_busy(true);
+    // 10 KB từ PC trong khi STM32 báo bận suốt
+    for _ in 0..200 {
+        rig.pc.inject(&[0x33; 50]);
+        rig.run_for(50_000);
+    }
+    assert!(rig.mcu.take_written().is_empty());
+    assert!(rig.bridge.upper_stats().buffer.dropped > 0);
+
+    // giao hết phần còn giữ: không quá một buffer trên không trung cộng một buffer chờ
+    rig.pins.set_busy(false);
+    rig.run_for(20_000_000);
+    let delivered = rig.mcu.take_written().len();
+    assert!(delivered > 0 && delivered < 3 * BUFF_SIZE, "delivered {delivered}");
+}
+
+#[test]
+fn flow_control_throttles_pc_with_hysteresis() {
+    let mut rig = Rig::new();
+    rig.bridge.set_flow_control(true);
+    rig.pins.set_busy(true);
+    let (high_water, low_water) = (BUFF_SIZE * 3 / 4, BUFF_SIZE / 4);
+
+    // dưới ngưỡng cao: vẫn đọc PC
+    rig.pc.inject(&[1; 100]);
+    rig.run_for(200_000);
+    assert!(!rig.bridge.pc_throttled());
+    // chạm ngưỡng cao: ngừng đọc, byte sau nằm lại ở UART0
+    rig.pc.inject(&[2; 100]);
+    rig.run_for(200_000);
+    assert!(200 >= high_water && rig.bridge.pc_throttled());
+    rig.pc.inject(&[3; 40]);
+    rig.run_for(200_000);
+    assert_eq!(rig.pc.pending_rx(), 40);
+
+    // giao một gói: còn 100 byte, giữa hai ngưỡng nên vẫn chặn
+    rig.pins.set_busy(false);
+    assert_eq!(rig.run_until(&rig.mcu.clone(), 100), [1; 100]);
+    rig.pins.set_busy(true);
+    rig.run_for(200_000);
+    assert!(100 > low_water && rig.bridge.pc_throttled());
+    assert_eq!(rig.pc.pending_rx(), 40);
+
+    // xuống dưới ngưỡng thấp: đọc lại PC, không mất byte nào
+    rig.pins.set_busy(false);
+    let delivered = rig.run_until(&rig.mcu.clone(), 140);
+    assert!(!rig.bridge.pc_throttled());
+    assert_eq!(delivered, [[2; 100].as_slice(), &[3; 40]].concat());
+    assert_eq!(rig.bridge.upper_stats().buffer.dropped, 0);
+}
+
+#[test]
+fn command_split_across_slow_loop_iterations() {
+    let mut rig = Rig::new();
+    rig.set_mode(true, true);
+    // firmware chạy vòng lặp 10 ms, lâu hơn timeout lệnh: byte được tính theo lúc tới
+    let t = rig.clock.now_us();
+    rig.mcu.inject_at(&[0xC1, 0xC1], t + 1_000);
+    rig.clock.advance(1_500);
+    rig.bridge.poll().unwrap();
+    rig.mcu.inject_at(&[0xC1], t + 2_500);
+    rig.clock.advance(10_000);
+    rig.bridge.poll().unwrap();
+    assert_eq!(rig.mcu.take_written(), [0xC0, 0x00, 0x00, 0x1A, 0x17, 0x44]);
+
+    // khoảng lặng thật giữa hai byte vẫn làm lệnh dở dang bị bỏ
+    rig.run_for(10_000);
+    let t = rig.clock.now_us();
+    rig.mcu.inject_at(&[0xC1, 0xC1], t);
+    rig.mcu.inject_at(&[0xC1], t + 8_000);
+    rig.clock.advance(10_000);
+    rig.bridge.poll().unwrap();
+    assert!(rig.mcu.take_written().is_empty());
+}
+
+#[test]
+fn frames_split_by_arrival_gap_within_one_poll() {
+    let mut rig = Rig::new();
+    rig.set_mode(true, true);
+    assert_eq!(rig.command(&[0xC0, 0x00, 0x05, 0x1A, 0x17, 0xC4], 6).len(), 6);
+    rig.set_mode(false, false);
+
+    // hai gói fixed cách nhau 10 ms nhưng vòng lặp chậm đọc cả hai một lần
+    let t = rig.clock.now_us();
+    rig.pc.inject_at(&[0x00, 0x05, 0x17, 1, 2, 3], t + 1_000);
+    rig.pc.inject_at(&[0x00, 0x05, 0x17, 4, 5], t + 11_000);
+    rig.clock.advance(20_000);
+    // gộp làm một thì header thứ hai thành dữ liệu
+    assert_eq!(rig.run_until(&rig.mcu.clone(), 5), [1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn mode_switch_drops_partial_frame() {
+    let mut rig = Rig::new();
+    rig.pc.inject(b"abc");
+    rig.step();
+    rig.set_mode(true, true);
+    rig.set_mode(false, false);
+    assert!(rig.mcu.take_written().is_empty());
+
+    rig.pc.inject(b"xy");
+    assert_eq!(rig.run_until(&rig.mcu.clone(), 2), b"xy");
+    rig.run_for(100_000);
+    assert!(rig.mcu.take_written().is_empty());
+}
+
+#[test]
+fn stats_count_reader_and_buffer_drops() {
+    let mut rig = Rig::new();
+    rig.pc.drop_rx(7);
+    rig.mcu.drop_rx(3);
+    rig.pc.inject(b"abc");
+    assert_eq!(rig.run_until(&rig.mcu.clone(), 3), b"abc");
+    rig.pc.drop_rx(1);
+    rig.step();
+
+    let upper = rig.bridge.upper_stats();
+    assert_eq!(upper.rx_dropped, 8);
+    assert_eq!((upper.buffer.pushed, upper.buffer.popped, upper.buffer.dropped), (3, 3, 0));
+    assert_eq!(rig.bridge.lower_stats().rx_dropped, 3);
+    assert_eq!(rig.bridge.lower_stats().buffer, Default::default());
+}
+
+#[test]
+fn sending_without_lbt_breaks_reception() {
+    for lbt in [false, true] {
+        let mut e22 = E32Module::new();
+        e22.set_variant(ModuleVariant::E22);
+        if lbt {
+            e22.set_state(E32State::Sleep);
+            e22.input_command(&[0xC0, 0x06, 0x01, 0x13], 4).unwrap();
+            e22.set_state(E32State::Normal);
+        }
+        let mut rig = Rig::with_module(e22);
+        rig.bridge.set_faults(FaultConfig { collisions: true, ..FaultConfig::none() });
+
+        // STM32 gửi khi gói từ PC đang trên không trung
+        rig.pc.inject(&[0x55; 60]);
+        rig.run_for(20_000);
+        rig.mcu.inject(b"pong");
+        assert_eq!(rig.run_until(&rig.pc.clone(), 4), b"pong");
+        let received = rig.mcu.take_written();
+        if lbt {
+            assert_eq!(received, [0x55; 60]);
+        } else {
+            assert!(received.is_empty(), "{received:02X?}");
+        }
+    }
+}
+
+#[test]
+fn rssi_command_only_on_e22_and_e220() {
+    let query = [0xC0, 0xC1, 0xC2, 0xC3, 0x00, 0x02];
+    // E32 không có lệnh này: 6 byte là dữ liệu thường
+    let mut rig = Rig::new();
+    rig.mcu.inject(&query);
+    assert_eq!(rig.run_until(&rig.pc.clone(), 6), query);
+    assert!(rig.mcu.take_written().is_empty());
+
+    let mut e22 = E32Module::new();
+    e22.set_variant(ModuleVariant::E22);
+    let mut rig = Rig::with_module(e22);
+    rig.mcu.inject(&query);
+    // nhiễu nền -120 dBm, chưa nhận gói nào
+    assert_eq!(rig.run_until(&rig.mcu.clone(), 5), [0xC1, 0x00, 0x02, 136, 0]);
+    rig.run_for(200_000);
+    assert!(rig.pc.take_written().is_empty());
+}
+
+#[test]
+fn e22_fixed_target_uses_full_channel_range() {
+    let target = [0x00, 0x01, 0x40, 1, 2];
+    // E32-433 chỉ tới kênh 0x1F: gói bị bỏ
+    let mut rig = Rig::new();
+    rig.set_mode(true, true);
+    assert_eq!(rig.command(&[0xC0, 0x00, 0x00, 0x1A, 0x17, 0xC4], 6).len(), 6);
+    rig.set_mode(false, false);
+    rig.mcu.inject(&target);
+    rig.run_for(200_000);
+    assert!(rig.pc.take_written().is_empty());
+
+    let mut e22 = E32Module::new();
+    e22.set_variant(ModuleVariant::E22);
+    e22.set_state(E32State::Sleep);
+    // REG3 bit 6: fixed transmission
+    e22.input_command(&[0xC0, 0x06, 0x01, 0x43], 4).unwrap();
+    e22.set_state(E32State::Normal);
+    let mut rig = Rig::with_module(e22);
+    rig.mcu.inject(&target);
+    assert_eq!(rig.run_until(&rig.pc.clone(), 5), target);
+}
@@ -0,0 +1,155 @@
+//! RingBuffer: quay vòng, chính sách tràn và bộ đếm
+
+use std::collections::VecDeque;
+
+use e32_simulator::buffer::*;
+
+fn contents<const N: usize>(buffer: &RingBuffer<u8, N>) -> Vec<u8> {
+    let (head, tail) = buffer.as_slices();
+    head.iter().chain(tail).copied().collect()
+}
+
+/// Đưa `first` tới vị trí `offset` với buffer rỗng
+fn rotated<const N: usize>(offset: usize) -> RingBuffer<u8, N> {
+    let mut buffer = RingBuffer::new();
+    buffer.push_slice(&vec![0; offset]);
+    buffer.pop_into(&mut vec![0; offset]);
+    buffer.reset_stats();
+    buffer
+}
+
+#[test]
+fn push_slice_and_pop_into_wrap_around() {
+    for offset in 0..8 {
+        let mut buffer = rotated::<8>(offset);
+        assert_eq!(buffer.push_slice(&[1, 2, 3, 4, 5, 6]), 6);
+        assert_eq!(contents(&buffer), [1, 2, 3, 4, 5, 6]);
+
+        let mut out = [0; 4];
+        assert_eq!(buffer.pop_into(&mut out), 4);
+        assert_eq!(out, [1, 2, 3, 4]);
+        assert_eq!(buffer.push_slice(&[7, 8, 9, 10, 11, 12, 13]), 6, "offset {offset}");
+
+        let mut out = [0; 10];
+        assert_eq!(buffer.pop_into(&mut out), 8);
+        assert_eq!(out[..8], [5, 6, 7, 8, 9, 10, 11, 12]);
+        assert!(buffer.is_empty());
+    }
+}
+
+#[test]
+fn as_slices_when_full_and_wrapped() {
+    let mut buffer = rotated::<5>(0);
+    buffer.push_slice(&[1, 2, 3, 4, 5]);
+    assert!(buffer.is_full());
+    assert_eq!(buffer.as_slices(), (&[1, 2, 3, 4, 5][..], &[][..]));
+
+    let mut buffer = rotated::<5>(3);
+    buffer.push_slice(&[1, 2, 3, 4, 5]);
+    assert!(buffer.is_full());
+    assert_eq!(buffer.as_slices(), (&[1, 2][..], &[3, 4, 5][..]));
+    assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+
+    // quay vòng nhưng chưa đầy
+    let mut buffer = rotated::<5>(4);
+    buffer.push_slice(&[1, 2, 3]);
+    assert_eq!(buffer.as_slices(), (&[1][..], &[2, 3][..]));
+    assert_eq!(buffer.peek(), Some(&1));
+}
+
+#[test]
+fn reject_counts_dropped_new_items() {
+    let mut buffer = rotated::<4>(2);
+    assert_eq!(buffer.push_slice(&[1, 2, 3, 4, 5, 6]), 4);
+    assert!(!buffer.enqueue(7));
+    assert_eq!(contents(&buffer), [1, 2, 3, 4]);
+    assert_eq!(*buffer.stats(), BufferStats { pushed: 4, popped: 0, dropped: 3, high_water: 4 });
+}
+
+#[test]
+fn overwrite_oldest_evicts_and_counts() {
+    let mut buffer: RingBuffer<u8, 4> = RingBuffer::with_policy(OverflowPolicy::OverwriteOldest);
+    buffer.push_slice(&[1, 2, 3]);
+    assert_eq!(buffer.push_slice(&[4, 5]), 2);
+    assert_eq!(contents(&buffer), [2, 3, 4, 5]);
+    assert_eq!(buffer.stats().dropped, 1);
+
+    assert!(buffer.enqueue(6));
+    assert_eq!(contents(&buffer), [3, 4, 5, 6]);
+    assert_eq!(buffer.stats().dropped, 2);
+
+    // dài hơn dung lượng: chỉ giữ N phần tử cuối, 4 cũ và 2 mới bị bỏ
+    assert_eq!(buffer.push_slice(&[7, 8, 9, 10, 11, 12]), 4);
+    assert_eq!(contents(&buffer), [9, 10, 11, 12]);
+    assert_eq!(*buffer.stats(), BufferStats { pushed: 10, popped: 0, dropped: 8, high_water: 4 });
+
+    // mọi phần tử đã thêm hoặc bị đè, hoặc đã lấy ra, hoặc còn trong buffer
+    assert_eq!(buffer.dequeue(), Some(9));
+    assert_eq!(buffer.stats().pushed, 6 + buffer.stats().popped + buffer.available() as u64);
+}
+
+#[test]
+fn zero_capacity() {
+    for policy in [OverflowPolicy::Reject, OverflowPolicy::OverwriteOldest, OverflowPolicy::Block { timeout_us: 0 }] {
+        let mut buffer: RingBuffer<u8, 0> = RingBuffer::with_policy(policy);
+        assert!(buffer.is_empty() && buffer.is_full());
+        assert_eq!(buffer.push_slice(&[1, 2, 3]), 0);
+        assert!(!buffer.enqueue(4));
+        assert_eq!(buffer.dequeue(), None);
+        assert_eq!(buffer.pop_into(&mut [0; 4]), 0);
+        assert_eq!(buffer.as_slices(), (&[][..], &[][..]));
+        assert_eq!(buffer.stats().dropped, 4);
+        assert_eq!(buffer.stats().pushed, 0);
+    }
+}
+
+#[test]
+fn high_water_and_reset() {
+    let mut buffer = rotated::<8>(0);
+    buffer.push_slice(&[1, 2, 3, 4, 5]);
+    buffer.pop_into(&mut [0; 3]);
+    assert_eq!(buffer.stats().high_water, 5);
+    buffer.reset_stats();
+    assert_eq!(*buffer.stats(), BufferStats { high_water: 2, ..BufferStats::default() });
+}
+
+/// So với VecDeque qua một chuỗi thao tác ngẫu nhiên (xorshift, cố định seed)
+#[test]
+fn matches_vecdeque_model() {
+    let mut buffer: RingBuffer<u8, 7> = RingBuffer::new();
+    let mut model = VecDeque::new();
+    let mut x = 12345u64;
+    for step in 0..20_000u32 {
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        let len = ((x >> 8) % 9) as usize;
+        match x % 5 {
+            0 => {
+                let item = step as u8;
+                assert_eq!(buffer.enqueue(item), model.len() < 7);
+                if model.len() < 7 {
+                    model.push_back(item);
+                }
+            }
+            1 => assert_eq!(buffer.dequeue(), model.pop_front()),
+            2 => {
+                let items: Vec<u8> = (0..len as u8).map(|i| i.wrapping_add(step as u8)).collect();
+                let n = buffer.push_slice(&items);
+                assert_eq!(n, len.min(7 - model.len()));
+                model.extend(&items[..n]);
+            }
+            3 => {
+                let mut out = vec![0; len];
+                let n = buffer.pop_into(&mut out);
+                assert_eq!(n, len.min(model.len()));
+                assert!(out[..n].iter().eq(model.drain(..n).collect::<Vec<_>>().iter()));
+            }
+            _ => {
+                assert_eq!(buffer.peek(), model.front());
+                assert!(buffer.iter().eq(model.iter()));
+            }
+        }
+        assert_eq!(buffer.available(), model.len());
+    }
+}
@@ -0,0 +1,110 @@
+//! Ghép lệnh cấu hình từng byte một, có timeout giữa hai byte
+
+use e32_simulator::command_parser::*;
+use e32_simulator::error::E32Error;
+use e32_simulator::register_map::ModuleVariant;
+
+/// Đưa từng byte, byte sau cách byte trước `gap_us`. Trả về các lệnh hoàn chỉnh.
+fn feed(parser: &mut CommandParser, bytes: &[u8], start_us: u64, gap_us: u64) -> Vec<Vec<u8>> {
+    let mut commands = Vec::new();
+    for (i, &byte) in bytes.iter().enumerate() {
+        if let Ok(Some(command)) = parser.push(byte, start_us + i as u64 * gap_us) {
+            commands.push(command.as_bytes().to_vec());
+        }
+    }
+    commands
+}
+
+#[test]
+fn e32_commands_byte_by_byte() {
+    let mut parser = CommandParser::new(ModuleVariant::E32);
+    for command in [&[0xC1, 0xC1, 0xC1][..], &[0xC3; 3], &[0xC4; 3], &[0xC0, 0, 1, 0x1A, 0x17, 0x44], &[0xC2, 0, 1, 0x1A, 0x17, 0x44]] {
+        for (i, &byte) in command.iter().enumerate() {
+            let result = parser.push(byte, i as u64 * 1000).unwrap();
+            if i + 1 < command.len() {
+                assert_eq!(result, None, "{command:02X?} byte {i}");
+            } else {
+                assert_eq!(result.unwrap().as_bytes(), command);
+            }
+        }
+    }
+}
+
+#[test]
+fn back_to_back_commands_split_correctly() {
+    let mut parser = CommandParser::new(ModuleVariant::E32);
+    let stream = [0xC1, 0xC1, 0xC1, 0xC0, 0, 0, 0x1A, 0x17, 0x44, 0xC3, 0xC3, 0xC3];
+    assert_eq!(
+        feed(&mut parser, &stream, 0, 100),
+        [vec![0xC1, 0xC1, 0xC1], vec![0xC0, 0, 0, 0x1A, 0x17, 0x44], vec![0xC3, 0xC3, 0xC3]]
+    );
+}
+
+#[test]
+fn bad_header_is_rejected_and_parser_recovers() {
+    let mut parser = CommandParser::new(ModuleVariant::E32);
+    assert!(matches!(parser.push(0x55, 0), Err(E32Error::BadHeader(0x55))));
+    assert_eq!(feed(&mut parser, &[0xC1; 3], 100, 100), [vec![0xC1; 3]]);
+}
+
+#[test]
+fn stale_partial_frame_times_out() {
+    let mut parser = CommandParser::new(ModuleVariant::E32);
+    assert!(feed(&mut parser, &[0xC0, 0x00, 0x00], 0, 500).is_empty());
+    // đúng bằng timeout vẫn còn khung
+    assert!(parser.expire(1000 + DEFAULT_COMMAND_TIMEOUT_US).is_ok());
+    assert!(matches!(
+        parser.expire(1001 + DEFAULT_COMMAND_TIMEOUT_US),
+        Err(E32Error::BadLength { expected: 6, actual: 3 })
+    ));
+    // phần còn lại tới muộn không ghép vào khung cũ
+    assert!(feed(&mut parser, &[0x1A, 0x17, 0x44], 20_000, 500).is_empty());
+}
+
+#[test]
+fn push_after_gap_starts_new_frame() {
+    let mut parser = CommandParser::new(ModuleVariant::E32);
+    assert!(feed(&mut parser, &[0xC1, 0xC1], 0, 1000).is_empty());
+    // byte tới sau timeout: khung cũ bị bỏ, C1 này mở khung mới
+    assert_eq!(feed(&mut parser, &[0xC1, 0xC1, 0xC1], 1000 + DEFAULT_COMMAND_TIMEOUT_US + 1, 1000), [vec![0xC1; 3]]);
+}
+
+#[test]
+fn configurable_timeout() {
+    let mut parser = CommandParser::new(ModuleVariant::E32);
+    parser.set_timeout_us(20_000);
+    assert_eq!(feed(&mut parser, &[0xC3; 3], 0, 15_000), [vec![0xC3; 3]]);
+    parser.set_timeout_us(500);
+    assert!(feed(&mut parser, &[0xC3; 3], 100_000, 600).is_empty());
+}
+
+#[test]
+fn clear_drops_partial_frame() {
+    let mut parser = CommandParser::new(ModuleVariant::E32);
+    assert!(feed(&mut parser, &[0xC0, 0, 0, 0x1A], 0, 100).is_empty());
+    parser.clear();
+    assert_eq!(feed(&mut parser, &[0xC1; 3], 500, 100), [vec![0xC1; 3]]);
+}
+
+#[test]
+fn register_commands_use_length_byte() {
+    for variant in [ModuleVariant::E22, ModuleVariant::E220] {
+        let mut parser = CommandParser::new(variant);
+        // đọc: C1 addr len
+        assert_eq!(feed(&mut parser, &[0xC1, 0x00, 0x09], 0, 100), [vec![0xC1, 0x00, 0x09]]);
+        // ghi: C0 addr len + len byte dữ liệu
+        let write = [0xC0, 0x03, 0x02, 0x62, 0x00];
+        assert_eq!(feed(&mut parser, &write, 1000, 100), [write.to_vec()]);
+        let temporary = [0xC2, 0x00, 0x01, 0x12];
+        assert_eq!(feed(&mut parser, &temporary, 2000, 100), [temporary.to_vec()]);
+    }
+}
+
+#[test]
+fn register_write_longer_than_buffer_is_rejected() {
+    let mut parser = CommandParser::new(ModuleVariant::E22);
+    assert_eq!(parser.push(0xC0, 0).unwrap(), None);
+    assert_eq!(parser.push(0x00, 100).unwrap(), None);
+    assert!(matches!(parser.push(0xFF, 200), Err(E32Error::BadLength { .. })));
+    assert_eq!(feed(&mut parser, &[0xC1, 0x00, 0x01], 300, 100), [vec![0xC1, 0x00, 0x01]]);
+}
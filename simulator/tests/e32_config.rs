@@ -0,0 +1,58 @@
+//! Cấu hình 6 byte của E32: mọi giá trị SPED và OPTION
+
+use e32_simulator::e32_module::*;
+use e32_simulator::error::E32Error;
+
+/// Mã air data rate 0b110/0b111 chỉ có trên E22/E220
+fn reserved_air_rate(sped: u8) -> bool {
+    sped & 0b111 >= 0b110
+}
+
+#[test]
+fn every_sped_and_option_round_trips() {
+    for head in [0xC0, 0xC2] {
+        for sped in 0..=u8::MAX {
+            for option in 0..=u8::MAX {
+                let params = [head, 0x12, 0x34, sped, 0x05, option];
+                match E32Config::try_from(params) {
+                    Ok(config) => {
+                        assert!(!reserved_air_rate(sped), "{params:02X?} accepted");
+                        assert_eq!(<[u8; CONF_SIZE]>::from(config), params);
+                    }
+                    Err(E32Error::ReservedValue { field: "air data rate", value }) => {
+                        assert!(reserved_air_rate(sped), "{params:02X?} rejected");
+                        assert_eq!(value, sped & 0b111);
+                    }
+                    Err(e) => panic!("{params:02X?}: {e}"),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn every_sped_and_option_reads_back_from_module() {
+    let mut e32 = E32Module::new();
+    e32.set_state(E32State::Sleep);
+    for sped in 0..=u8::MAX {
+        for option in 0..=u8::MAX {
+            let command = [0xC2, 0x00, 0x01, sped, 0x17, option];
+            let response = e32.input_command(&command, command.len());
+            if reserved_air_rate(sped) {
+                assert!(matches!(response, Err(E32Error::ReservedValue { field: "air data rate", .. })));
+                continue;
+            }
+            assert_eq!(response.unwrap().as_bytes(), command);
+            // C1 C1 C1 luôn trả header C0, phần còn lại giữ nguyên từng byte
+            let read = e32.input_command(&[0xC1; 3], 3).unwrap();
+            assert_eq!(read.as_bytes(), [0xC0, 0x00, 0x01, sped, 0x17, option]);
+        }
+    }
+}
+
+#[test]
+fn unknown_header_is_rejected() {
+    for head in (0..=u8::MAX).filter(|h| ![0xC0, 0xC2].contains(h)) {
+        assert!(matches!(E32Config::try_from([head, 0, 0, 0x1A, 0x17, 0x44]), Err(E32Error::BadHeader(h)) if h == head));
+    }
+}
@@ -0,0 +1,215 @@
+//! Các VirtualNode nối qua một ether chạy trên localhost
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use e32_simulator::band::*;
+use e32_simulator::e32_module::*;
+use e32_simulator::ether::*;
+use e32_simulator::fault::*;
+use e32_simulator::link::AirTarget;
+use e32_simulator::link_budget::*;
+use e32_simulator::mock::MockClock;
+use e32_simulator::register_map::ModuleVariant;
+use e32_simulator::virtual_node::*;
+
+type Node = VirtualNode<MockClock>;
+
+/// Ether trên cổng ngẫu nhiên và một node cho mỗi module
+fn network(modules: Vec<E32Module>) -> (Vec<Node>, MockClock, EtherPeers) {
+    let ether = Ether::bind("127.0.0.1:0").unwrap();
+    let addr = ether.local_addr().unwrap();
+    let peers = ether.peers();
+    ether.spawn();
+    let clock = MockClock::new();
+    let nodes: Vec<Node> = modules.into_iter().map(|e32| VirtualNode::connect(addr, e32, clock.clone()).unwrap()).collect();
+    wait_for_configs(&peers, nodes.iter().map(|node| node.ether_config()));
+    (nodes, clock, peers)
+}
+
+/// Chờ tới khi ether dùng đúng các cấu hình này, tối đa 5 s
+fn wait_for_configs(peers: &EtherPeers, expected: impl IntoIterator<Item = NodeConfig>) {
+    let expected: Vec<_> = expected.into_iter().collect();
+    let count = |configs: &[NodeConfig], config: &NodeConfig| configs.iter().filter(|c| *c == config).count();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        let configs = peers.configs();
+        // thứ tự node trong ether không cố định, chỉ so số lần xuất hiện
+        if configs.len() == expected.len() && expected.iter().all(|c| count(&configs, c) == count(&expected, c)) {
+            return;
+        }
+        assert!(Instant::now() < deadline, "ether has {configs:?}, want {expected:?}");
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// Chờ ether nhận cấu hình hiện tại của mọi node
+fn sync(nodes: &[Node], peers: &EtherPeers) {
+    wait_for_configs(peers, nodes.iter().map(|node| node.ether_config()));
+}
+
+/// Chạy 100 ms giả lập, đủ cho một gói ngắn ở 2.4k
+fn run(nodes: &mut [Node], clock: &MockClock) {
+    run_ms(nodes, clock, 100);
+}
+
+fn run_ms(nodes: &mut [Node], clock: &MockClock, ms: u64) {
+    for _ in 0..ms {
+        clock.advance(1000);
+        for node in nodes.iter_mut() {
+            node.poll().unwrap();
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn received(node: &mut Node) -> Vec<u8> {
+    let mut buf = [0u8; 256];
+    let n = node.read(&mut buf);
+    buf[..n].to_vec()
+}
+
+#[test]
+fn delivers_only_on_same_channel() {
+    let (mut nodes, clock, peers) = network(vec![E32Module::new(), E32Module::new(), E32Module::new()]);
+    nodes[2].set_state(E32State::Sleep);
+    nodes[2].command(&[0xC0, 0, 0, 0x1A, 0x18, 0x44]).unwrap();
+    nodes[2].set_state(E32State::Normal);
+    sync(&nodes, &peers);
+
+    nodes[0].write(b"ping");
+    run(&mut nodes, &clock);
+    assert_eq!(received(&mut nodes[1]), b"ping");
+    assert!(received(&mut nodes[2]).is_empty());
+}
+
+#[test]
+fn simultaneous_frames_collide() {
+    let (mut nodes, clock, _) = network(vec![E32Module::new(), E32Module::new(), E32Module::new()]);
+    nodes[2].set_faults(FaultConfig { collisions: true, ..FaultConfig::none() });
+
+    nodes[0].write(b"ping");
+    nodes[1].write(b"pong");
+    run(&mut nodes, &clock);
+    assert!(received(&mut nodes[2]).is_empty());
+}
+
+#[test]
+fn out_of_range_node_hears_nothing() {
+    let (mut nodes, clock, _) = network(vec![E32Module::new(), E32Module::new(), E32Module::new()]);
+    nodes[1].set_placement(Placement::new(1000.0, 0.0));
+    nodes[2].set_placement(Placement::new(1_000_000.0, 0.0));
+
+    nodes[0].write(b"ping");
+    run(&mut nodes, &clock);
+    assert_eq!(received(&mut nodes[1]), b"ping");
+    assert!(received(&mut nodes[2]).is_empty());
+}
+
+#[test]
+fn different_band_hears_nothing() {
+    let mut e868 = E32Module::new();
+    e868.set_band(Band::Band868);
+    let (mut nodes, clock, _) = network(vec![E32Module::new(), E32Module::new(), e868]);
+
+    nodes[0].write(b"ping");
+    run(&mut nodes, &clock);
+    assert_eq!(received(&mut nodes[1]), b"ping");
+    assert!(received(&mut nodes[2]).is_empty());
+}
+
+fn e22() -> E32Module {
+    let mut e32 = E32Module::new();
+    e32.set_variant(ModuleVariant::E22);
+    e32
+}
+
+/// Ghi thanh ghi ở chế độ Sleep rồi về Normal
+fn write_registers(node: &mut Node, command: &[u8]) {
+    node.set_state(E32State::Sleep);
+    assert_eq!(node.command(command).unwrap().as_bytes()[0], 0xC1);
+    node.set_state(E32State::Normal);
+}
+
+#[test]
+fn e22_needs_same_net_id_and_key() {
+    let (mut nodes, clock, peers) = network(vec![e22(), e22(), e22(), e22()]);
+    write_registers(&mut nodes[2], &[0xC0, 0x02, 0x01, 0x05]);
+    write_registers(&mut nodes[3], &[0xC0, 0x07, 0x02, 0x12, 0x34]);
+    sync(&nodes, &peers);
+
+    nodes[0].write(b"ping");
+    run(&mut nodes, &clock);
+    assert_eq!(received(&mut nodes[1]), b"ping");
+    assert!(received(&mut nodes[2]).is_empty());
+    assert!(received(&mut nodes[3]).is_empty());
+}
+
+#[test]
+fn lbt_waits_for_clear_channel() {
+    for lbt in [false, true] {
+        let (mut nodes, clock, peers) = network(vec![e22(), e22(), e22()]);
+        if lbt {
+            // REG3 mặc định 0x03, bit 4 bật LBT
+            write_registers(&mut nodes[1], &[0xC0, 0x06, 0x01, 0x13]);
+        }
+        nodes[2].set_faults(FaultConfig { collisions: true, ..FaultConfig::none() });
+        sync(&nodes, &peers);
+
+        // node 1 muốn phát khi gói dài của node 0 đang trên không trung
+        nodes[0].write(&[0x55; 40]);
+        run_ms(&mut nodes, &clock, 20);
+        nodes[1].write(b"pong");
+        run_ms(&mut nodes, &clock, 1000);
+        let got = received(&mut nodes[2]);
+        if lbt {
+            assert_eq!(got.len(), 44, "{got:02X?}");
+            assert!(got.ends_with(b"pong"));
+        } else {
+            assert!(got.is_empty(), "{got:02X?}");
+        }
+    }
+}
+
+#[test]
+fn stalled_node_does_not_block_other_links() {
+    let ether = Ether::bind("127.0.0.1:0").unwrap();
+    let addr = ether.local_addr().unwrap();
+    let peers = ether.peers();
+    ether.spawn();
+    let config = |channel, frequency_khz| NodeConfig { address: 0, channel, air_data_rate: 2400, frequency_khz, net_id: 0, crypt_key: 0 };
+    let frame = |channel, frequency_khz, payload: Vec<u8>| AirFrame {
+        target: AirTarget { address: 0xFFFF, channel },
+        airtime_us: 1000,
+        tx_power_dbm: 20,
+        x_m: 0.0,
+        y_m: 0.0,
+        frequency_khz,
+        payload,
+    };
+
+    // kênh 0x17: `stalled` không bao giờ đọc, `flooder` phát tới khi ether ghi vào nó bị chặn
+    let stalled = EtherNode::connect(addr, config(0x17, 433_000)).unwrap();
+    let mut flooder = EtherNode::connect(addr, config(0x17, 433_000)).unwrap();
+    // kênh 0x18: một cặp node khác
+    let mut sender = EtherNode::connect(addr, config(0x18, 434_000)).unwrap();
+    let mut listener = EtherNode::connect(addr, config(0x18, 434_000)).unwrap();
+    wait_for_configs(&peers, [config(0x17, 433_000), config(0x17, 433_000), config(0x18, 434_000), config(0x18, 434_000)]);
+    thread::spawn(move || {
+        let flood = frame(0x17, 433_000, vec![0x55; 8000]);
+        while flooder.send(&flood).is_ok() {}
+    });
+    thread::sleep(Duration::from_millis(500));
+
+    sender.send(&frame(0x18, 434_000, b"ping".to_vec())).unwrap();
+    let mut got = None;
+    for _ in 0..2000 {
+        got = listener.try_recv().unwrap();
+        if got.is_some() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(got.map(|f| f.payload), Some(b"ping".to_vec()));
+    drop(stalled);
+}
@@ -0,0 +1,86 @@
+//! Tiêm lỗi với seed cố định: tỉ lệ mất gói, lỗi bit, lỗi chùm và tính lặp lại
+
+use e32_simulator::fault::*;
+
+fn injector(config: FaultConfig) -> FaultInjector {
+    FaultInjector::new(FaultConfig { seed: 42, ..config })
+}
+
+/// Số bit khác nhau giữa gói gốc và gói đã qua tiêm lỗi
+fn flipped_bits(original: &[u8], packet: &[u8]) -> u32 {
+    original.iter().zip(packet).map(|(a, b)| (a ^ b).count_ones()).sum()
+}
+
+#[test]
+fn none_leaves_packets_intact() {
+    let mut faults = injector(FaultConfig::none());
+    for _ in 0..1_000 {
+        let mut packet = [0xA5; 58];
+        assert!(faults.apply(&mut packet));
+        assert_eq!(packet, [0xA5; 58]);
+    }
+}
+
+#[test]
+fn loss_rate_drops_that_share_of_packets() {
+    let mut faults = injector(FaultConfig { loss_rate: 0.3, ..FaultConfig::none() });
+    let lost = (0..10_000).filter(|_| !faults.apply(&mut [0; 10])).count();
+    assert!((2_800..3_200).contains(&lost), "lost {lost}");
+
+    let mut faults = injector(FaultConfig { loss_rate: 1.0, ..FaultConfig::none() });
+    assert!((0..100).all(|_| !faults.apply(&mut [0; 10])));
+}
+
+#[test]
+fn bit_error_rate_flips_that_share_of_bits() {
+    let mut faults = injector(FaultConfig { bit_error_rate: 0.01, ..FaultConfig::none() });
+    let mut flipped = 0;
+    // 400 000 bit, kỳ vọng 4000 bit lật
+    for _ in 0..1_000 {
+        let mut packet = [0; 50];
+        assert!(faults.apply(&mut packet));
+        flipped += flipped_bits(&[0; 50], &packet);
+    }
+    assert!((3_700..4_300).contains(&flipped), "flipped {flipped}");
+}
+
+#[test]
+fn burst_flips_consecutive_bits() {
+    let mut faults = injector(FaultConfig { burst_rate: 1.0, burst_len: 8, ..FaultConfig::none() });
+    for _ in 0..1_000 {
+        let mut packet = [0u8; 20];
+        assert!(faults.apply(&mut packet));
+        let bits: Vec<bool> = packet.iter().flat_map(|b| (0..8).map(move |i| b & (0x80 >> i) != 0)).collect();
+        let first = bits.iter().position(|&b| b).expect("burst flipped nothing");
+        let count = bits.iter().filter(|&&b| b).count();
+        // lỗi chùm bắt đầu gần cuối gói thì bị cắt ở bit cuối
+        assert_eq!(count, 8.min(bits.len() - first), "{packet:02x?}");
+        assert!(bits[first..first + count].iter().all(|&b| b));
+    }
+
+    let mut faults = injector(FaultConfig { burst_rate: 0.5, burst_len: 8, ..FaultConfig::none() });
+    let hit = (0..1_000)
+        .filter(|_| {
+            let mut packet = [0u8; 20];
+            faults.apply(&mut packet);
+            packet != [0; 20]
+        })
+        .count();
+    assert!((430..570).contains(&hit), "hit {hit}");
+}
+
+#[test]
+fn same_seed_gives_same_faults() {
+    let config = FaultConfig { loss_rate: 0.2, bit_error_rate: 0.005, burst_rate: 0.1, burst_len: 12, ..FaultConfig::none() };
+    let run = |seed: u64| {
+        let mut faults = FaultInjector::new(FaultConfig { seed, ..config });
+        (0..200)
+            .map(|i| {
+                let mut packet = [i as u8; 32];
+                faults.apply(&mut packet).then_some(packet)
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(run(7), run(7));
+    assert_ne!(run(7), run(8));
+}
@@ -0,0 +1,76 @@
+//! Tách khung theo khoảng lặng với đồng hồ giả lập
+
+use e32_simulator::framer::*;
+use e32_simulator::hal::Parity;
+
+/// 9600 8N1: 1042 us mỗi ký tự, lặng 3 ký tự là 3126 us
+fn framer() -> IdleFramer {
+    IdleFramer::new(9600, Parity::None)
+}
+
+#[test]
+fn char_time_counts_parity_bit() {
+    assert_eq!(char_time_us(9600, Parity::None), 1042);
+    assert_eq!(char_time_us(9600, Parity::Even), 1146);
+    assert_eq!(char_time_us(115200, Parity::None), 87);
+}
+
+#[test]
+fn frame_closes_after_idle_time() {
+    let mut framer = framer();
+    assert_eq!(framer.poll(0), None);
+    framer.on_bytes(4, 10_000);
+    framer.on_bytes(2, 12_000);
+    assert_eq!(framer.poll(12_000 + 3_125), None);
+    assert_eq!(framer.poll(12_000 + 3_126), Some(6));
+    assert_eq!(framer.poll(100_000), None);
+}
+
+#[test]
+fn gap_between_reads_closes_frame_before_poll() {
+    let mut framer = framer();
+    framer.on_bytes(3, 10_000);
+    // byte đầu của lần đọc sau tới lúc 14_000 - 1042, lặng 2916 us: chưa đủ
+    framer.on_bytes(2, 14_000);
+    // byte đầu tới lúc 20_000, sau byte cuối 6000 us
+    framer.on_bytes(1, 20_000);
+    framer.on_bytes(4, 40_000);
+    // cả ba khung đều được đọc trong một vòng lặp, vẫn trả về đúng thứ tự
+    assert_eq!(framer.poll(40_001), Some(5));
+    assert_eq!(framer.poll(40_001), Some(1));
+    assert_eq!(framer.poll(40_001), None);
+    assert_eq!(framer.poll(50_000), Some(4));
+}
+
+#[test]
+fn arrival_estimate_counts_back_from_last_byte() {
+    let framer = framer();
+    assert_eq!(framer.arrival_us(10_000, 2, 3), 10_000);
+    assert_eq!(framer.arrival_us(10_000, 0, 3), 10_000 - 2 * 1042);
+    assert_eq!(framer.arrival_us(1_000, 0, 3), 0);
+}
+
+#[test]
+fn clear_drops_open_and_closed_frames() {
+    let mut framer = framer();
+    framer.on_bytes(3, 1_000);
+    framer.on_bytes(3, 100_000);
+    framer.clear();
+    assert_eq!(framer.poll(1_000_000), None);
+    framer.on_bytes(2, 2_000_000);
+    assert_eq!(framer.poll(2_100_000), Some(2));
+}
+
+#[test]
+fn idle_chars_and_line_change_idle_time() {
+    let mut framer = framer();
+    framer.set_idle_chars(0);
+    assert_eq!(framer.idle_us(), 1042);
+    framer.set_idle_chars(10);
+    assert_eq!(framer.idle_us(), 10_420);
+    framer.set_line(115200, Parity::Odd);
+    assert_eq!(framer.idle_us(), 10 * char_time_us(115200, Parity::Odd));
+    framer.on_bytes(1, 0);
+    assert_eq!(framer.poll(framer.idle_us() - 1), None);
+    assert_eq!(framer.poll(framer.idle_us()), Some(1));
+}
@@ -0,0 +1,52 @@
+//! Air time của gói con và lịch phát nối tiếp của `LinkModel`
+
+use std::collections::VecDeque;
+
+use e32_simulator::e32_module::{AirDataRate, ForwardErrorCorrection};
+use e32_simulator::link::*;
+
+#[test]
+fn airtime_at_known_rates() {
+    // 2.4k: preamble 4 byte 13333 us, 10 byte dữ liệu 33333 us
+    assert_eq!(airtime_us(AirDataRate::Rate2400, ForwardErrorCorrection::Off, 10, 0), 46_666);
+    // FEC: 100 bit trên không thay vì 80
+    assert_eq!(airtime_us(AirDataRate::Rate2400, ForwardErrorCorrection::On, 10, 0), 54_999);
+    // 19.2k, gói con đầy 58 byte: 1666 + 24166 us
+    assert_eq!(airtime_us(AirDataRate::Rate19200, ForwardErrorCorrection::Off, SUB_PACKET_SIZE, 0), 25_832);
+    // 300 bps, một byte: 106666 + 26666 us
+    assert_eq!(airtime_us(AirDataRate::Rate300, ForwardErrorCorrection::Off, 1, 0), 133_332);
+}
+
+#[test]
+fn airtime_adds_wake_up_preamble() {
+    let plain = airtime_us(AirDataRate::Rate2400, ForwardErrorCorrection::On, 10, 0);
+    assert_eq!(airtime_us(AirDataRate::Rate2400, ForwardErrorCorrection::On, 10, 250_000), plain + 250_000);
+}
+
+#[test]
+fn send_splits_into_back_to_back_sub_packets() {
+    let (rate, fec) = (AirDataRate::Rate19200, ForwardErrorCorrection::Off);
+    let mut link = LinkModel::new();
+    let mut queue = VecDeque::new();
+    link.send(1_000, &[0x55; 100], rate, fec, 5_000, &mut queue);
+
+    let first = 1_000 + 5_000 + airtime_us(rate, fec, SUB_PACKET_SIZE, 0);
+    let second = first + airtime_us(rate, fec, 100 - SUB_PACKET_SIZE, 0);
+    let packets: Vec<_> = queue.iter().map(|p| (p.done_us, p.data.len())).collect();
+    assert_eq!(packets, [(first, SUB_PACKET_SIZE), (second, 100 - SUB_PACKET_SIZE)]);
+}
+
+#[test]
+fn send_waits_for_channel_to_clear() {
+    let (rate, fec) = (AirDataRate::Rate2400, ForwardErrorCorrection::Off);
+    let mut link = LinkModel::new();
+    let mut queue = VecDeque::new();
+    link.send(0, &[1; 10], rate, fec, 0, &mut queue);
+    // gói sau đưa vào khi gói trước còn trên không: phát nối tiếp, không chồng
+    link.send(10_000, &[2; 10], rate, fec, 0, &mut queue);
+    assert_eq!(queue[1].done_us, 2 * 46_666);
+
+    // kênh đã rảnh thì phát ngay
+    link.send(200_000, &[3; 10], rate, fec, 0, &mut queue);
+    assert_eq!(queue[2].done_us, 246_666);
+}
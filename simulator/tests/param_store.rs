@@ -0,0 +1,125 @@
+//! Lưu tham số C0 qua FileParamStore, nạp lại khi khởi động
+
+use std::path::PathBuf;
+
+use e32_simulator::bridge::*;
+use e32_simulator::e32_module::*;
+use e32_simulator::hal::Parity;
+use e32_simulator::mock::*;
+use e32_simulator::param_store::*;
+use e32_simulator::register_map::ModuleVariant;
+
+/// File riêng cho mỗi test, xoá khi hết phạm vi
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("e32_params_{}_{name}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        Self(path)
+    }
+
+    fn module(&self) -> E32Module {
+        let mut e32 = E32Module::with_store(Box::new(FileParamStore::new(&self.0)));
+        e32.set_state(E32State::Sleep);
+        e32
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn read_params(e32: &mut E32Module) -> Vec<u8> {
+    e32.input_command(&[0xC1; 3], 3).unwrap().as_bytes().to_vec()
+}
+
+#[test]
+fn c0_persists_and_c2_does_not() {
+    let file = TempFile::new("c0_c2");
+    let mut e32 = file.module();
+    assert_eq!(read_params(&mut e32), [0xC0, 0x00, 0x00, 0x1A, 0x17, 0x44]);
+    let saved = [0xC0, 0x12, 0x34, 0x3A, 0x05, 0xC4];
+    e32.input_command(&saved, 6).unwrap();
+    e32.input_command(&[0xC2, 0x00, 0x01, 0x1A, 0x17, 0x44], 6).unwrap();
+    assert_eq!(std::fs::read(&file.0).unwrap(), saved);
+
+    // khởi động lại: giữ C0, bỏ C2
+    let mut e32 = file.module();
+    assert_eq!(read_params(&mut e32), saved);
+}
+
+#[test]
+fn missing_or_bad_file_uses_defaults() {
+    let file = TempFile::new("bad");
+    let mut store = FileParamStore::new(&file.0);
+    assert_eq!(store.load().unwrap(), None);
+
+    std::fs::write(&file.0, [0xC0, 0x00]).unwrap();
+    assert_eq!(store.load().unwrap(), Some(vec![0xC0, 0x00]));
+    assert_eq!(read_params(&mut file.module()), [0xC0, 0x00, 0x00, 0x1A, 0x17, 0x44]);
+
+    // đúng độ dài nhưng air data rate không hợp lệ
+    std::fs::write(&file.0, [0xC0, 0x00, 0x00, 0x1F, 0x17, 0x44]).unwrap();
+    assert_eq!(read_params(&mut file.module()), [0xC0, 0x00, 0x00, 0x1A, 0x17, 0x44]);
+}
+
+#[test]
+fn register_image_persists_for_e22_and_e220() {
+    for (variant, size, reg3) in [(ModuleVariant::E22, 9, 0x06), (ModuleVariant::E220, 8, 0x05)] {
+        let file = TempFile::new("registers");
+        let module = || {
+            let mut e32 = file.module();
+            e32.set_variant(variant);
+            e32
+        };
+        let mut e32 = module();
+        // ghi lưu ADDH ADDL, ghi tạm REG3
+        assert_eq!(e32.input_command(&[0xC0, 0x00, 0x02, 0x12, 0x34], 5).unwrap().as_bytes(), [0xC1, 0x00, 0x02, 0x12, 0x34]);
+        e32.input_command(&[0xC2, reg3, 0x01, 0x43], 4).unwrap();
+        assert_eq!(std::fs::read(&file.0).unwrap().len(), size);
+        // lệnh sai định dạng không ghi đè ảnh đã lưu
+        e32.input_command(&[0xC0, 0x00, 0x20, 0x00], 4).unwrap();
+
+        let mut e32 = module();
+        assert_eq!(e32.address(), 0x1234);
+        assert_eq!(e32.config().fixed_transmission, FixedTransmission::Transparent);
+        let read = e32.input_command(&[0xC1, reg3, 0x01], 3).unwrap();
+        assert_eq!(read.as_bytes(), [0xC1, reg3, 0x01, 0x03]);
+    }
+}
+
+#[test]
+fn image_of_other_variant_is_ignored() {
+    let file = TempFile::new("variant");
+    let mut e32 = file.module();
+    e32.set_variant(ModuleVariant::E22);
+    e32.input_command(&[0xC0, 0x00, 0x02, 0x12, 0x34], 5).unwrap();
+
+    let mut e32 = file.module();
+    assert_eq!(read_params(&mut e32), [0xC0, 0x00, 0x00, 0x1A, 0x17, 0x44]);
+    e32.set_variant(ModuleVariant::E220);
+    assert_eq!(e32.address(), 0);
+}
+
+/// Cổng UART1 mở ở baud khác mặc định để thấy bridge luôn đặt lại
+fn boot(e32: E32Module) -> MockSerial {
+    let mcu = MockSerial::new(115200);
+    let pc = MockSerial::new(PC_BAUDRATE);
+    Bridge::new(pc, mcu.clone(), MockModePins::new(), MockAux::new(), MockClock::new(), e32).unwrap();
+    mcu
+}
+
+#[test]
+fn bridge_boots_with_saved_uart_config() {
+    let file = TempFile::new("boot");
+    let mcu = boot(file.module());
+    assert_eq!((mcu.baudrate(), mcu.parity()), (9600, Parity::None));
+
+    // SPED 0x7A: 8O1, 115200 bps
+    file.module().input_command(&[0xC0, 0x00, 0x00, 0x7A, 0x17, 0x44], 6).unwrap();
+    let mcu = boot(file.module());
+    assert_eq!((mcu.baudrate(), mcu.parity()), (115200, Parity::Odd));
+}
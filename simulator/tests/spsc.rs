@@ -0,0 +1,105 @@
+//! Hàng đợi SPSC: quay vòng chỉ số và một bên ghi, một bên đọc trên hai thread
+
+use std::thread;
+
+use e32_simulator::spsc::*;
+
+#[test]
+fn split_only_once() {
+    let queue: SpscQueue<4> = SpscQueue::new();
+    assert!(queue.split().is_some());
+    assert!(queue.split().is_none());
+}
+
+#[test]
+fn wraps_indices_with_odd_capacity() {
+    // N không phải lũy thừa của 2: chỉ số chạy trong [0, 2N) phải quay vòng đúng
+    let queue: SpscQueue<7> = SpscQueue::new();
+    let (mut producer, mut consumer) = queue.split().unwrap();
+    let (mut sent, mut received) = (0usize, 0usize);
+    for round in 0..100 {
+        let bytes: Vec<u8> = (sent..sent + round % 9).map(|i| i as u8).collect();
+        let pushed = producer.push_slice(&bytes, round as u64);
+        assert_eq!(pushed, bytes.len().min(7 - (sent - received)));
+        sent += pushed;
+        assert_eq!(producer.free() + consumer.available(), 7);
+
+        let mut out = [0u8; 5];
+        let n = consumer.pop_into(&mut out[..round % 6]);
+        assert_eq!(n, (round % 6).min(sent - received));
+        assert!(out[..n].iter().copied().eq((received..received + n).map(|i| i as u8)));
+        received += n;
+    }
+}
+
+#[test]
+fn pop_timed_stops_at_next_arrival() {
+    let queue: SpscQueue<8> = SpscQueue::new();
+    let (mut producer, mut consumer) = queue.split().unwrap();
+    assert_eq!(consumer.pop_timed(&mut [0; 8]), (0, 0));
+    // lần ghi thứ ba quay vòng qua cuối mảng
+    producer.push_slice(&[1, 2, 3], 100);
+    producer.push_slice(&[4, 5], 200);
+    let mut out = [0u8; 8];
+    assert_eq!(consumer.pop_timed(&mut out), (3, 100));
+    assert_eq!(out[..3], [1, 2, 3]);
+    producer.push_slice(&[6, 7, 8, 9], 300);
+    assert_eq!(consumer.pop_timed(&mut out[..1]), (1, 200));
+    assert_eq!(consumer.pop_timed(&mut out), (1, 200));
+    assert_eq!(out[0], 5);
+    assert_eq!(consumer.pop_timed(&mut out), (4, 300));
+    assert_eq!(out[..4], [6, 7, 8, 9]);
+}
+
+#[test]
+fn dropped_count_is_taken_once() {
+    let queue: SpscQueue<4> = SpscQueue::new();
+    let (mut producer, mut consumer) = queue.split().unwrap();
+    let pushed = producer.push_slice(&[1, 2, 3, 4, 5, 6], 0);
+    producer.record_dropped(6 - pushed);
+    producer.record_dropped(1);
+    assert_eq!(consumer.take_dropped(), 3);
+    assert_eq!(consumer.take_dropped(), 0);
+}
+
+/// Bên ghi và bên đọc chạy song song với kích thước khối khác nhau,
+/// bên đọc phải thấy đúng dãy byte, không mất, không lặp
+#[test]
+fn two_threads_keep_order() {
+    const TOTAL: usize = 1_000_000;
+    let queue: SpscQueue<61> = SpscQueue::new();
+    let (mut producer, mut consumer) = queue.split().unwrap();
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            let mut sent = 0;
+            let mut chunk = [0u8; 23];
+            while sent < TOTAL {
+                let len = (1 + sent % 23).min(TOTAL - sent);
+                for (i, byte) in chunk[..len].iter_mut().enumerate() {
+                    *byte = ((sent + i) % 251) as u8;
+                }
+                let pushed = producer.push_slice(&chunk[..len], sent as u64);
+                sent += pushed;
+                if pushed == 0 {
+                    // máy test có thể chỉ có một CPU
+                    thread::yield_now();
+                }
+            }
+        });
+        scope.spawn(move || {
+            let mut received = 0;
+            let mut out = [0u8; 17];
+            while received < TOTAL {
+                let n = consumer.pop_into(&mut out[..1 + received % 17]);
+                for (i, byte) in out[..n].iter().enumerate() {
+                    assert_eq!(*byte, ((received + i) % 251) as u8, "byte {}", received + i);
+                }
+                received += n;
+                if n == 0 {
+                    thread::yield_now();
+                }
+            }
+            assert_eq!(consumer.available(), 0);
+        });
+    });
+}
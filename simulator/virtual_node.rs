@@ -0,0 +1,213 @@
+//! Node E32 ảo trên host: E32Module nối vào ether thay vì một radio thật.
+//! Firmware giả lập ghi/đọc qua `write`/`read` như qua UART của module.
+
+use std::collections::VecDeque;
+use std::io;
+use std::net::ToSocketAddrs;
+
+use super::e32_module::*;
+use super::ether::*;
+use super::fault::*;
+use super::hal::Clock;
+use super::link::*;
+use super::link_budget::*;
+
+/// Gói đang nhận, giao cho firmware khi hết air time nếu không va chạm
+struct Reception {
+    end_us: u64,
+    rssi_dbm: f64,
+    payload: Vec<u8>,
+    collided: bool,
+}
+
+pub struct VirtualNode<C> {
+    e32: E32Module,
+    ether: EtherNode,
+    link: LinkModel,
+    clock: C,
+    faults: FaultInjector,
+    placement: Placement,
+    tx_air: VecDeque<(AirTarget, InFlight)>,
+    tx_until_us: u64, // gói con đã lên không trung phát xong lúc nào
+    receiving: Vec<Reception>,
+    received: VecDeque<u8>,
+}
+
+impl<C: Clock> VirtualNode<C> {
+    pub fn connect(addr: impl ToSocketAddrs, e32: E32Module, clock: C) -> io::Result<Self> {
+        let ether = EtherNode::connect(addr, Self::node_config(&e32))?;
+        Ok(Self {
+            e32,
+            ether,
+            link: LinkModel::new(),
+            clock,
+            faults: FaultInjector::new(FaultConfig::none()),
+            placement: Placement::default(),
+            tx_air: VecDeque::new(),
+            tx_until_us: 0,
+            receiving: Vec::new(),
+            received: VecDeque::new(),
+        })
+    }
+
+    /// Cấu hình vô tuyến node này báo cho ether
+    pub fn ether_config(&self) -> NodeConfig {
+        Self::node_config(&self.e32)
+    }
+
+    fn node_config(e32: &E32Module) -> NodeConfig {
+        NodeConfig {
+            address: e32.address(),
+            channel: e32.channel(),
+            air_data_rate: e32.config().air_data_rate.as_bps(),
+            frequency_khz: e32.frequency_khz().unwrap_or(0),
+            net_id: e32.net_id(),
+            crypt_key: e32.crypt_key(),
+        }
+    }
+
+    pub fn e32(&self) -> &E32Module {
+        &self.e32
+    }
+
+    pub fn set_faults(&mut self, config: FaultConfig) {
+        self.faults = FaultInjector::new(config);
+    }
+
+    pub fn set_placement(&mut self, placement: Placement) {
+        self.placement = placement;
+    }
+
+    /// Đổi chế độ như khi firmware đặt M0/M1
+    pub fn set_state(&mut self, state: E32State) {
+        self.e32.set_state(state);
+    }
+
+    /// Lệnh cấu hình (chỉ ở chế độ Sleep), báo cấu hình mới cho ether.
+    /// Lệnh bị từ chối trả về `E32Error`, lỗi kết nối ether trả về `io::Error`.
+    pub fn command(&mut self, command: &[u8]) -> anyhow::Result<E32Response> {
+        let response = self.e32.input_command(command, command.len())?;
+        self.ether.set_config(Self::node_config(&self.e32))?;
+        Ok(response)
+    }
+
+    /// Firmware gửi một gói. Fixed: 3 byte đầu là ADDH ADDL CHAN của đích.
+    /// Chỉ phát ở chế độ Normal và WakeUp.
+    pub fn write(&mut self, frame: &[u8]) {
+        if let Some(response) = self.e32.rssi_query(frame) {
+            self.received.extend(response.as_bytes());
+            return;
+        }
+        if !matches!(self.e32.state(), E32State::Normal | E32State::WakeUp) {
+            return;
+        }
+        let (target, payload) = if self.e32.config().fixed_transmission == FixedTransmission::PointToPoint {
+            match AirTarget::split_fixed(frame) {
+                Some(split) => split,
+                None => return,
+            }
+        } else {
+            (AirTarget { address: self.e32.address(), channel: self.e32.channel() }, frame)
+        };
+        // kênh đích ngoài dải tần của module thì không phát được
+        if !self.e32.is_valid_channel(target.channel) {
+            return;
+        }
+        let mut packets = VecDeque::new();
+        let now = self.clock.now_us();
+        let config = self.e32.config();
+        self.link.send(now, payload, config.air_data_rate, config.fec, 0, &mut packets);
+        self.tx_air.extend(packets.into_iter().map(|p| (target, p)));
+    }
+
+    /// Đọc dữ liệu đã nhận, trả về số byte
+    pub fn read(&mut self, buf: &mut [u8]) -> usize {
+        let n = buf.len().min(self.received.len());
+        for (dst, src) in buf.iter_mut().zip(self.received.drain(..n)) {
+            *dst = src;
+        }
+        n
+    }
+
+    /// Lúc kênh hết bận, `None` nếu không có gói nào đang trên không trung
+    fn channel_busy_until(&self, now: u64) -> Option<u64> {
+        self.receiving.iter().map(|r| r.end_us).filter(|&end| end > now).max()
+    }
+
+    /// Phát các gói con tới lượt, nhận gói từ ether và giao các gói đã nhận xong
+    pub fn poll(&mut self) -> io::Result<()> {
+        let now = self.clock.now_us();
+        let (air_data_rate, fec) = (self.e32.config().air_data_rate, self.e32.config().fec);
+
+        // gửi lên ether lúc bắt đầu phát, bên nhận tự chờ air time
+        while let Some((target, packet)) = self.tx_air.front() {
+            let airtime_us = airtime_us(air_data_rate, fec, packet.data.len(), 0);
+            if now + airtime_us < packet.done_us {
+                break;
+            }
+            // LBT: kênh đang có gói khác thì lùi các gói con chờ phát tới khi kênh trống
+            if let Some(busy_until) = self.channel_busy_until(now).filter(|_| self.e32.lbt_enabled()) {
+                let delay_us = busy_until - (packet.done_us - airtime_us);
+                for (_, packet) in self.tx_air.iter_mut() {
+                    packet.done_us += delay_us;
+                }
+                break;
+            }
+            let frame = AirFrame {
+                target: *target,
+                airtime_us: airtime_us as u32,
+                tx_power_dbm: self.e32.config().transmission_power.as_dbm() as i8,
+                x_m: self.placement.x_m as f32,
+                y_m: self.placement.y_m as f32,
+                frequency_khz: self.e32.channel_frequency_khz(target.channel).unwrap_or(0),
+                payload: packet.data.clone(),
+            };
+            self.ether.send(&frame)?;
+            self.tx_until_us = packet.done_us;
+            self.tx_air.pop_front();
+        }
+
+        while let Some(frame) = self.ether.try_recv()? {
+            let from = Placement::new(frame.x_m as f64, frame.y_m as f64);
+            let rssi_dbm = self.placement.rssi_from(&from, frame.tx_power_dbm as f64, frame.frequency_khz);
+            // dưới độ nhạy thu: không nhận được, cũng không gây va chạm
+            if rssi_dbm < air_data_rate.sensitivity_dbm() {
+                continue;
+            }
+            let mut reception = Reception {
+                end_us: now + frame.airtime_us as u64,
+                rssi_dbm,
+                payload: frame.payload,
+                collided: false,
+            };
+            if self.faults.config().collisions {
+                // đang phát (bán song công) hoặc đang nhận gói khác thì va chạm
+                if now < self.tx_until_us {
+                    reception.collided = true;
+                }
+                for other in self.receiving.iter_mut().filter(|r| r.end_us > now) {
+                    other.collided = true;
+                    reception.collided = true;
+                }
+            }
+            self.receiving.push(reception);
+        }
+
+        let mut i = 0;
+        while i < self.receiving.len() {
+            if now < self.receiving[i].end_us {
+                i += 1;
+                continue;
+            }
+            let mut reception = self.receiving.remove(i);
+            if !reception.collided && self.faults.apply(&mut reception.payload) {
+                self.e32.record_signal_rssi(reception.rssi_dbm);
+                self.received.extend(reception.payload);
+                self.received.extend(self.e32.rssi_byte());
+            }
+        }
+        let environment = self.receiving.iter().map(|r| r.rssi_dbm).fold(NOISE_FLOOR_DBM, f64::max);
+        self.e32.record_environment_rssi(environment);
+        Ok(())
+    }
+}